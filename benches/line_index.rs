@@ -0,0 +1,62 @@
+//! Compares the line helpers on a plain string, which scan the text on every
+//! call, with the same helpers on a `TextBuffer`, which use its line index.
+//!
+//! Run with `cargo bench --bench line_index`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rust_terminal_notepad::{line_length, position_to_index, total_lines, TextBuffer, TextLines};
+
+const LINES: usize = 100_000;
+const LOOKUPS: usize = 200;
+
+fn sample_text() -> String {
+    (0..LINES)
+        .map(|i| format!("line {i}: the quick brown fox jumps over the lazy dog\n"))
+        .collect()
+}
+
+// The lookups a single key press does: cursor to offset, line length, line count
+fn lookups<T: TextLines + ?Sized>(text: &T) -> Duration {
+    let start = Instant::now();
+    for i in 0..LOOKUPS {
+        let y = (i * 7919) % LINES;
+        black_box(position_to_index(text, (10, y)));
+        black_box(line_length(text, y));
+        black_box(total_lines(text));
+    }
+    start.elapsed()
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{name:<28} {:>10.3} ms total {:>10.3} us per key press",
+        elapsed.as_secs_f64() * 1e3,
+        elapsed.as_secs_f64() * 1e6 / LOOKUPS as f64
+    );
+}
+
+fn main() {
+    let text = sample_text();
+    let buffer = TextBuffer::from(text.as_str());
+    println!("{LINES} lines, {} bytes, {LOOKUPS} lookups", text.len());
+
+    let scan = lookups(text.as_str());
+    report("str (full scan)", scan);
+    let indexed = lookups(&buffer);
+    report("TextBuffer (line index)", indexed);
+    println!(
+        "speedup: {:.0}x",
+        scan.as_secs_f64() / indexed.as_secs_f64().max(f64::EPSILON)
+    );
+
+    // typing in the middle of the file keeps the index up to date
+    let mut buffer = buffer;
+    let start = Instant::now();
+    for i in 0..LOOKUPS {
+        let index = position_to_index(&buffer, (5, LINES / 2));
+        buffer.insert(index, if i % 40 == 0 { "\n" } else { "x" });
+    }
+    report("TextBuffer (insert)", start.elapsed());
+}
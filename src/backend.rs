@@ -0,0 +1,117 @@
+use std::io::Write;
+use std::time::Duration;
+
+use crossterm::{
+    cursor,
+    event::{self, Event},
+    queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self},
+};
+
+/// Where the editor gets its input events from.
+pub trait EventSource {
+    /// Waits at most `timeout` for input, `true` when `read_event` would not
+    /// block.
+    fn poll(&mut self, timeout: Duration) -> std::io::Result<bool>;
+    /// Blocks until the next event arrives. `None` means the input is closed.
+    fn read_event(&mut self) -> std::io::Result<Option<Event>>;
+}
+
+/// Everything the editor needs from the screen it draws on.
+pub trait Backend {
+    /// Screen size as (columns, rows).
+    fn size(&self) -> std::io::Result<(u16, u16)>;
+    fn clear(&mut self) -> std::io::Result<()>;
+    fn move_to(&mut self, x: u16, y: u16) -> std::io::Result<()>;
+    /// Prints `text` at the current position, it must not contain line breaks.
+    fn print(&mut self, text: &str) -> std::io::Result<()>;
+    fn set_cursor_visible(&mut self, visible: bool) -> std::io::Result<()>;
+    /// Swaps the colours of the text printed from now on, used for the
+    /// selection.
+    fn set_reversed(&mut self, reversed: bool) -> std::io::Result<()>;
+    /// Makes everything queued so far visible to the user.
+    fn flush(&mut self) -> std::io::Result<()>;
+
+    /// Starts a frame, the screen may hold back what is drawn until
+    /// `end_update` so the user never sees half of it.
+    fn begin_update(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Ends the frame started by `begin_update`.
+    fn end_update(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads events from the real terminal.
+pub struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn poll(&mut self, timeout: Duration) -> std::io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read_event(&mut self) -> std::io::Result<Option<Event>> {
+        event::read().map(Some)
+    }
+}
+
+/// Draws on the real terminal, commands are queued until `flush`.
+pub struct CrosstermBackend<W: Write> {
+    out: W,
+}
+
+impl<W: Write> CrosstermBackend<W> {
+    pub fn new(out: W) -> Self {
+        CrosstermBackend { out }
+    }
+}
+
+impl<W: Write> Backend for CrosstermBackend<W> {
+    fn size(&self) -> std::io::Result<(u16, u16)> {
+        terminal::size()
+    }
+
+    fn clear(&mut self) -> std::io::Result<()> {
+        queue!(self.out, terminal::Clear(terminal::ClearType::All))
+    }
+
+    fn move_to(&mut self, x: u16, y: u16) -> std::io::Result<()> {
+        queue!(self.out, cursor::MoveTo(x, y))
+    }
+
+    fn print(&mut self, text: &str) -> std::io::Result<()> {
+        queue!(self.out, Print(text))
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) -> std::io::Result<()> {
+        if visible {
+            queue!(self.out, cursor::Show)
+        } else {
+            queue!(self.out, cursor::Hide)
+        }
+    }
+
+    fn set_reversed(&mut self, reversed: bool) -> std::io::Result<()> {
+        if reversed {
+            queue!(self.out, SetAttribute(Attribute::Reverse))
+        } else {
+            queue!(self.out, SetAttribute(Attribute::NoReverse))
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+
+    // terminals without synchronized updates ignore the sequence
+    fn begin_update(&mut self) -> std::io::Result<()> {
+        queue!(self.out, terminal::BeginSynchronizedUpdate)
+    }
+
+    fn end_update(&mut self) -> std::io::Result<()> {
+        queue!(self.out, terminal::EndSynchronizedUpdate)
+    }
+}
@@ -0,0 +1,416 @@
+use std::fmt;
+use std::ops::Range;
+
+/// Upper bound for the text kept in a single node, in bytes.
+const MAX_CHUNK: usize = 1024;
+
+type Link = Option<Box<Node>>;
+
+/// A rope node: a chunk of text plus aggregates of its whole subtree.
+///
+/// Nodes form a treap ordered by text position and heap-ordered by
+/// `priority`, which keeps the tree balanced in expectation.
+struct Node {
+    chunk: String,
+    priority: u32,
+    newlines: usize,
+    subtree_len: usize,
+    subtree_newlines: usize,
+    left: Link,
+    right: Link,
+}
+
+impl Node {
+    fn new(chunk: String, priority: u32) -> Box<Node> {
+        let mut node = Box::new(Node {
+            newlines: count_newlines(&chunk),
+            chunk,
+            priority,
+            subtree_len: 0,
+            subtree_newlines: 0,
+            left: None,
+            right: None,
+        });
+        node.update();
+        node
+    }
+
+    fn update(&mut self) {
+        self.subtree_len = len(&self.left) + self.chunk.len() + len(&self.right);
+        self.subtree_newlines = newlines(&self.left) + self.newlines + newlines(&self.right);
+    }
+}
+
+fn len(link: &Link) -> usize {
+    link.as_ref().map_or(0, |node| node.subtree_len)
+}
+
+fn newlines(link: &Link) -> usize {
+    link.as_ref().map_or(0, |node| node.subtree_newlines)
+}
+
+fn count_newlines(text: &str) -> usize {
+    text.bytes().filter(|&b| b == b'\n').count()
+}
+
+fn merge(left: Link, right: Link) -> Link {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority >= right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+/// Splits the tree so that the left part holds exactly `offset` bytes.
+fn split(link: Link, offset: usize) -> (Link, Link) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    let left_len = len(&node.left);
+    if offset <= left_len {
+        let (left, right) = split(node.left.take(), offset);
+        node.left = right;
+        node.update();
+        (left, Some(node))
+    } else if offset >= left_len + node.chunk.len() {
+        let (left, right) = split(node.right.take(), offset - left_len - node.chunk.len());
+        node.right = left;
+        node.update();
+        (Some(node), right)
+    } else {
+        // the tail keeps the priority, so it may own the old right subtree
+        let tail = node.chunk.split_off(offset - left_len);
+        let mut tail = Node::new(tail, node.priority);
+        tail.right = node.right.take();
+        tail.update();
+        node.newlines = count_newlines(&node.chunk);
+        node.update();
+        (Some(node), Some(tail))
+    }
+}
+
+/// Inserts into the chunk containing `offset` when it has room for `text`.
+fn insert_in_place(link: &mut Link, offset: usize, text: &str) -> bool {
+    let Some(node) = link else {
+        return false;
+    };
+    let left_len = len(&node.left);
+    let inserted = if offset < left_len {
+        insert_in_place(&mut node.left, offset, text)
+    } else if offset > left_len + node.chunk.len() {
+        insert_in_place(&mut node.right, offset - left_len - node.chunk.len(), text)
+    } else if node.chunk.len() + text.len() <= MAX_CHUNK {
+        node.chunk.insert_str(offset - left_len, text);
+        node.newlines += count_newlines(text);
+        true
+    } else {
+        false
+    };
+    if inserted {
+        node.update();
+    }
+    inserted
+}
+
+/// Removes `range` when it lies inside one chunk without emptying it.
+fn remove_in_place(link: &mut Link, range: Range<usize>) -> bool {
+    let Some(node) = link else {
+        return false;
+    };
+    let left_len = len(&node.left);
+    let chunk_end = left_len + node.chunk.len();
+    let removed = if range.end <= left_len {
+        remove_in_place(&mut node.left, range)
+    } else if range.start >= chunk_end {
+        remove_in_place(
+            &mut node.right,
+            range.start - chunk_end..range.end - chunk_end,
+        )
+    } else if range.start >= left_len && range.end <= chunk_end && range.len() < node.chunk.len() {
+        let removed: String = node
+            .chunk
+            .drain(range.start - left_len..range.end - left_len)
+            .collect();
+        node.newlines -= count_newlines(&removed);
+        true
+    } else {
+        false
+    };
+    if removed {
+        node.update();
+    }
+    removed
+}
+
+/// Text storage for the editor.
+///
+/// A rope made of chunks of at most `MAX_CHUNK` bytes, inserts, deletes and
+/// line lookups cost O(log n) instead of rewriting or scanning the whole text.
+/// All offsets are byte offsets and have to fall on char boundaries.
+pub struct TextBuffer {
+    root: Link,
+    seed: u32,
+}
+
+impl Default for TextBuffer {
+    fn default() -> Self {
+        TextBuffer::new()
+    }
+}
+
+impl TextBuffer {
+    pub fn new() -> Self {
+        TextBuffer {
+            root: None,
+            seed: 0x9e37_79b9,
+        }
+    }
+
+    /// Length of the text in bytes.
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lines, an empty buffer has one empty line.
+    pub fn line_count(&self) -> usize {
+        newlines(&self.root) + 1
+    }
+
+    /// Inserts `text` at byte `offset`.
+    pub fn insert(&mut self, offset: usize, text: &str) {
+        assert!(offset <= self.len(), "insert offset out of bounds");
+        if text.is_empty() || insert_in_place(&mut self.root, offset, text) {
+            return;
+        }
+        let (left, right) = split(self.root.take(), offset);
+        let middle = self.build(text);
+        self.root = merge(merge(left, middle), right);
+    }
+
+    /// Removes the bytes in `range`.
+    pub fn remove(&mut self, range: Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "remove range out of bounds"
+        );
+        if range.is_empty() || remove_in_place(&mut self.root, range.clone()) {
+            return;
+        }
+        let (left, rest) = split(self.root.take(), range.start);
+        let (_, right) = split(rest, range.len());
+        self.root = merge(left, right);
+    }
+
+    /// Byte offset where line `line` starts, the end of the text past the last line.
+    pub fn line_start(&self, line: usize) -> usize {
+        if line == 0 {
+            return 0;
+        }
+        if line >= self.line_count() {
+            return self.len();
+        }
+        // find the end of the `line`-th newline
+        let mut link = &self.root;
+        let mut wanted = line;
+        let mut base = 0;
+        while let Some(node) = link {
+            let left_newlines = newlines(&node.left);
+            if wanted <= left_newlines {
+                link = &node.left;
+                continue;
+            }
+            wanted -= left_newlines;
+            base += len(&node.left);
+            if wanted <= node.newlines {
+                let (index, _) = node
+                    .chunk
+                    .match_indices('\n')
+                    .nth(wanted - 1)
+                    .expect("newline count out of sync");
+                return base + index + 1;
+            }
+            wanted -= node.newlines;
+            base += node.chunk.len();
+            link = &node.right;
+        }
+        self.len()
+    }
+
+    /// Byte offset where line `line` ends, not counting its line break.
+    pub fn line_end(&self, line: usize) -> usize {
+        if line + 1 >= self.line_count() {
+            self.len()
+        } else {
+            self.line_start(line + 1) - 1
+        }
+    }
+
+    /// Index of the line containing byte `offset`.
+    pub fn line_of(&self, offset: usize) -> usize {
+        let mut link = &self.root;
+        let mut offset = offset.min(self.len());
+        let mut line = 0;
+        while let Some(node) = link {
+            let left_len = len(&node.left);
+            if offset <= left_len {
+                link = &node.left;
+                continue;
+            }
+            line += newlines(&node.left);
+            offset -= left_len;
+            if offset <= node.chunk.len() {
+                return line + count_newlines(&node.chunk[..offset]);
+            }
+            line += node.newlines;
+            offset -= node.chunk.len();
+            link = &node.right;
+        }
+        line
+    }
+
+    /// Whether byte `offset` starts a character or is the end of the text.
+    pub fn is_char_boundary(&self, offset: usize) -> bool {
+        if offset > self.len() {
+            return false;
+        }
+        // chunks are split on character boundaries, only their insides matter
+        let mut link = &self.root;
+        let mut offset = offset;
+        while let Some(node) = link {
+            let left_len = len(&node.left);
+            if offset < left_len {
+                link = &node.left;
+                continue;
+            }
+            offset -= left_len;
+            if offset <= node.chunk.len() {
+                return node.chunk.is_char_boundary(offset);
+            }
+            offset -= node.chunk.len();
+            link = &node.right;
+        }
+        true
+    }
+
+    /// Text of line `line` without its line break.
+    pub fn line(&self, line: usize) -> String {
+        self.slice(self.line_start(line)..self.line_end(line))
+    }
+
+    /// Copies the bytes in `range` out of the buffer.
+    pub fn slice(&self, range: Range<usize>) -> String {
+        let mut out = String::with_capacity(range.len());
+        collect(&self.root, 0, &range, &mut out);
+        out
+    }
+
+    /// Iterates over the stored chunks in text order.
+    pub fn chunks(&self) -> Chunks<'_> {
+        let mut chunks = Chunks { stack: Vec::new() };
+        chunks.push_left(&self.root);
+        chunks
+    }
+
+    fn build(&mut self, text: &str) -> Link {
+        let mut link = None;
+        let mut rest = text;
+        while !rest.is_empty() {
+            let mut end = rest.len().min(MAX_CHUNK);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            let (chunk, tail) = rest.split_at(end);
+            let priority = self.next_priority();
+            link = merge(link, Some(Node::new(chunk.to_string(), priority)));
+            rest = tail;
+        }
+        link
+    }
+
+    // xorshift, the priorities only need to look random
+    fn next_priority(&mut self) -> u32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed
+    }
+}
+
+fn collect(link: &Link, base: usize, range: &Range<usize>, out: &mut String) {
+    let Some(node) = link else {
+        return;
+    };
+    if range.end <= base || range.start >= base + node.subtree_len {
+        return;
+    }
+    collect(&node.left, base, range, out);
+    let start = base + len(&node.left);
+    let end = start + node.chunk.len();
+    if range.start < end && range.end > start {
+        let from = range.start.max(start) - start;
+        let to = range.end.min(end) - start;
+        out.push_str(&node.chunk[from..to]);
+    }
+    collect(&node.right, end, range, out);
+}
+
+impl From<&str> for TextBuffer {
+    fn from(text: &str) -> Self {
+        let mut buffer = TextBuffer::new();
+        buffer.root = buffer.build(text);
+        buffer
+    }
+}
+
+impl fmt::Display for TextBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for TextBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TextBuffer({:?})", self.to_string())
+    }
+}
+
+/// In-order iterator over the chunks of a `TextBuffer`.
+pub struct Chunks<'a> {
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Chunks<'a> {
+    fn push_left(&mut self, mut link: &'a Link) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        Some(&node.chunk)
+    }
+}
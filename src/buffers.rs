@@ -0,0 +1,153 @@
+//! Several files open at once.
+//!
+//! Every file has its own `Editor`, one of them is on the screen and gets
+//! the keys. `Ctrl+PageDown` and `Ctrl+PageUp` switch to the next and the
+//! previous one, and quitting an editor closes only its file until the last
+//! one is left.
+
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::clipboard::{self, InternalClipboard, SharedClipboard};
+use crate::editor::{Action, Editor};
+
+/// The open editors and the one on the screen.
+pub struct Buffers {
+    editors: Vec<Editor>,
+    current: usize,
+}
+
+impl Buffers {
+    /// Starts with the first of `editors` on the screen, all of them
+    /// sharing one internal clipboard.
+    ///
+    /// # Panics
+    ///
+    /// When `editors` is empty.
+    pub fn new(editors: Vec<Editor>) -> Self {
+        assert!(!editors.is_empty(), "at least one editor is needed");
+        let mut buffers = Buffers {
+            editors,
+            current: 0,
+        };
+        buffers.set_clipboard(clipboard::shared(InternalClipboard::new()));
+        buffers
+    }
+
+    pub fn len(&self) -> usize {
+        self.editors.len()
+    }
+
+    /// Always `false`, the last editor is never closed.
+    pub fn is_empty(&self) -> bool {
+        self.editors.is_empty()
+    }
+
+    /// Index of the editor on the screen.
+    pub fn index(&self) -> usize {
+        self.current
+    }
+
+    /// The editor on the screen.
+    pub fn current(&self) -> &Editor {
+        &self.editors[self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut Editor {
+        &mut self.editors[self.current]
+    }
+
+    pub fn editors(&self) -> &[Editor] {
+        &self.editors
+    }
+
+    pub fn editors_mut(&mut self) -> &mut [Editor] {
+        &mut self.editors
+    }
+
+    /// Puts editor `index` on the screen and says which file it is.
+    pub fn switch_to(&mut self, index: usize) {
+        self.current = index % self.editors.len();
+        let (number, count) = (self.current + 1, self.editors.len());
+        let editor = &mut self.editors[self.current];
+        let message = format!("Buffer {number}/{count}: {}", editor.file_name());
+        editor.show_message(message);
+    }
+
+    /// Makes every editor cut, copy and paste with `clipboard`.
+    pub fn set_clipboard(&mut self, clipboard: SharedClipboard) {
+        for editor in &mut self.editors {
+            editor.set_clipboard(clipboard.clone());
+        }
+    }
+
+    /// Makes every editor keep its undo history in `dir`.
+    pub fn set_undo_dir(&mut self, dir: Option<PathBuf>) {
+        for editor in &mut self.editors {
+            editor.set_undo_dir(dir.clone());
+        }
+    }
+
+    /// Tells every editor the size of the screen.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        for editor in &mut self.editors {
+            editor.resize(width, height);
+        }
+    }
+
+    /// Applies a terminal event to the editor on the screen, or switches
+    /// to another one.
+    pub fn handle_event(&mut self, event: Event) -> Action {
+        match event {
+            Event::Key(KeyEvent {
+                code: code @ (KeyCode::PageDown | KeyCode::PageUp),
+                modifiers: KeyModifiers::CONTROL,
+                kind,
+                ..
+            }) if self.editors.len() > 1 => {
+                if kind != KeyEventKind::Release {
+                    let step = if code == KeyCode::PageDown {
+                        1
+                    } else {
+                        self.editors.len() - 1
+                    };
+                    self.switch_to(self.current + step);
+                }
+                Action::Continue
+            }
+            Event::Resize(width, height) => {
+                self.resize(width, height);
+                Action::Continue
+            }
+            event => {
+                let action = self.current_mut().handle_event(event);
+                if action == Action::Quit && self.editors.len() > 1 {
+                    self.editors.remove(self.current);
+                    self.switch_to(self.current.min(self.editors.len() - 1));
+                    return Action::Continue;
+                }
+                action
+            }
+        }
+    }
+
+    /// Lets the timers of every editor fire.
+    pub fn tick(&mut self, now: Instant) {
+        for editor in &mut self.editors {
+            editor.tick(now);
+        }
+    }
+
+    /// The earliest moment `tick` has something to do for any editor.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.editors.iter().filter_map(Editor::next_deadline).min()
+    }
+}
+
+impl From<Editor> for Buffers {
+    fn from(editor: Editor) -> Self {
+        Buffers::new(vec![editor])
+    }
+}
@@ -0,0 +1,254 @@
+//! Command line arguments of the editor.
+
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::buffers::Buffers;
+use crate::config::Config;
+use crate::editor::Editor;
+use crate::encoding::Encoding;
+use crate::error::{self, EditorError};
+
+/// File edited when none is given.
+pub const DEFAULT_FILE: &str = "untitled.txt";
+
+/// Printed for `--help`.
+pub const HELP: &str = "\
+Usage: rust_terminal_notepad [OPTIONS] [[+LINE] FILE[:LINE[:COLUMN]]]...
+
+Edits every FILE in its own buffer, untitled.txt when none is given.
+A FILE of - reads the text from standard input.
+
+Options:
+  +LINE                open the next file at line LINE
+  -R, --readonly       open the files without allowing changes
+      --encoding ENC   read and write the files in ENC: utf-8 or latin1
+      --config FILE    read the settings from FILE
+  -h, --help           print this help and exit
+  -V, --version        print the version and exit
+
+Keys:
+  Ctrl+S  save           Ctrl+Q  save and quit     Esc  quit
+  Ctrl+X  cut            Ctrl+C  copy              Ctrl+V  paste
+  Ctrl+Z  undo           Ctrl+Y  redo
+  Alt+Z   toggle wrap    Alt+L   line numbers
+  Ctrl+Left / Ctrl+Right       previous / next word
+  Ctrl+Up / Ctrl+Down          previous / next paragraph
+  Alt+A / Alt+E                previous / next sentence
+  Ctrl+Backspace or Ctrl+H     delete the word before
+  Ctrl+Delete                  delete the word after
+  Shift+motion                 select        Ctrl+A  select all
+  Ctrl+PageDown / Ctrl+PageUp  next / previous buffer
+";
+
+/// `rust_terminal_notepad` and its version, printed for `--version`.
+pub fn version() -> String {
+    format!("rust_terminal_notepad {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// What the command line asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Version,
+    Edit(Options),
+}
+
+/// How and what to edit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    /// The files in the order they were given, empty when there were none.
+    pub files: Vec<FileArg>,
+    pub read_only: bool,
+    pub encoding: Encoding,
+    /// Configuration file given instead of the default one.
+    pub config: Option<PathBuf>,
+}
+
+/// A file to edit and where to put the cursor in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileArg {
+    /// Path of the file, `None` for standard input.
+    pub path: Option<String>,
+    /// Line and column, both counted from 1.
+    pub position: Option<(usize, usize)>,
+}
+
+/// A command line that cannot be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    UnknownOption(String),
+    MissingValue(String),
+    UnknownEncoding(String),
+    InvalidLine(String),
+    /// `+LINE` with no file after it.
+    NoFileForLine(String),
+    /// `-` given more than once.
+    StdinTwice,
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(option) => write!(f, "unknown option '{option}'"),
+            CliError::MissingValue(option) => write!(f, "option '{option}' needs a value"),
+            CliError::UnknownEncoding(name) => {
+                write!(f, "unsupported encoding '{name}', use utf-8 or latin1")
+            }
+            CliError::InvalidLine(argument) => write!(f, "invalid line number in '{argument}'"),
+            CliError::NoFileForLine(argument) => write!(f, "'{argument}' needs a file after it"),
+            CliError::StdinTwice => write!(f, "standard input can only be read once"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Parses the arguments after the program name.
+pub fn parse<I>(args: I) -> Result<Command, CliError>
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into);
+    let mut options = Options::default();
+    let mut line = None;
+    let mut only_files = false;
+
+    while let Some(arg) = args.next() {
+        if !only_files && arg.len() > 1 && arg.starts_with('-') {
+            // `--name=value` and `--name value` are the same
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError::MissingValue(name.to_string()))
+            };
+            match name {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-V" | "--version" => return Ok(Command::Version),
+                "-R" | "--readonly" => options.read_only = true,
+                "--encoding" => {
+                    let encoding = value()?;
+                    options.encoding = Encoding::from_name(&encoding)
+                        .ok_or(CliError::UnknownEncoding(encoding))?;
+                }
+                "--config" => options.config = Some(PathBuf::from(value()?)),
+                "--" => only_files = true,
+                _ => return Err(CliError::UnknownOption(arg)),
+            }
+        } else if let Some(number) = arg.strip_prefix('+').filter(|_| !only_files) {
+            line = Some((parse_number(number, &arg)?, arg.clone()));
+        } else if arg == "-" {
+            if options.files.iter().any(|file| file.path.is_none()) {
+                return Err(CliError::StdinTwice);
+            }
+            options.files.push(FileArg {
+                path: None,
+                position: line.take().map(|(line, _)| (line, 1)),
+            });
+        } else {
+            let (path, position) = split_position(&arg);
+            options.files.push(FileArg {
+                path: Some(path),
+                position: position.or(line.take().map(|(line, _)| (line, 1))),
+            });
+        }
+    }
+
+    if let Some((_, argument)) = line {
+        return Err(CliError::NoFileForLine(argument));
+    }
+    Ok(Command::Edit(options))
+}
+
+fn parse_number(number: &str, argument: &str) -> Result<usize, CliError> {
+    match number.parse() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(CliError::InvalidLine(argument.to_string())),
+    }
+}
+
+/// Splits `file:line` and `file:line:column` unless a file is really called
+/// that.
+fn split_position(arg: &str) -> (String, Option<(usize, usize)>) {
+    if !Path::new(arg).exists() {
+        let mut parts = arg.rsplitn(3, ':');
+        let numbers: Vec<&str> = parts.by_ref().take(2).collect();
+        let number = |part: &str| part.parse::<usize>().ok().filter(|&n| n > 0);
+        match (numbers.as_slice(), parts.next()) {
+            ([column, line], Some(path)) if !path.is_empty() => {
+                if let (Some(line), Some(column)) = (number(line), number(column)) {
+                    return (path.to_string(), Some((line, column)));
+                }
+            }
+            _ => {}
+        }
+        if let Some((path, line)) = arg.rsplit_once(':') {
+            if let (false, Some(line)) = (path.is_empty(), number(line)) {
+                return (path.to_string(), Some((line, 1)));
+            }
+        }
+    }
+    (arg.to_string(), None)
+}
+
+/// Opens the files `options` asks for, reading `-` from `stdin`, with the
+/// settings of the configuration file.
+pub fn open<R: Read>(options: &Options, stdin: &mut R) -> error::Result<Buffers> {
+    let config = match &options.config {
+        Some(path) => Config::load(path)?,
+        None => Config::load_default()?,
+    };
+
+    let default = [FileArg {
+        path: Some(DEFAULT_FILE.to_string()),
+        position: None,
+    }];
+    let files = if options.files.is_empty() {
+        &default[..]
+    } else {
+        &options.files[..]
+    };
+
+    let mut editors = Vec::new();
+    for file in files {
+        let mut editor = match &file.path {
+            Some(path) => Editor::open_with_encoding(path, options.encoding)?,
+            None => {
+                let mut bytes = Vec::new();
+                stdin.read_to_end(&mut bytes)?;
+                let text = options
+                    .encoding
+                    .decode(bytes)
+                    .ok_or_else(|| EditorError::InvalidEncoding("standard input".into()))?;
+                let mut editor = Editor::from_text(&untitled_name(), &text);
+                editor.set_encoding(options.encoding);
+                editor
+            }
+        };
+        editor.set_read_only(options.read_only);
+        config.apply(&mut editor);
+        if let Some((line, column)) = file.position {
+            editor.go_to(column - 1, line - 1);
+        }
+        editors.push(editor);
+    }
+    Ok(Buffers::new(editors))
+}
+
+/// A name for text from standard input that does not overwrite a file.
+fn untitled_name() -> String {
+    (1..)
+        .map(|n| match n {
+            1 => DEFAULT_FILE.to_string(),
+            n => format!("untitled-{n}.txt"),
+        })
+        .find(|name| !Path::new(name).exists())
+        .unwrap()
+}
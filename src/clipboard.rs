@@ -0,0 +1,247 @@
+//! Where cut and copied text goes and pasted text comes from.
+//!
+//! `InternalClipboard` keeps the text inside the program and always works.
+//! `SystemClipboard` keeps it there too and also hands it to the desktop:
+//! with an OSC 52 escape sequence, which most terminals turn into a
+//! clipboard update even over ssh, and with a tool like `wl-copy` or
+//! `xclip` when one is installed. Editors share a clipboard through
+//! `SharedClipboard`, so text cut in one buffer can be pasted in another.
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+
+/// What the editor needs from a clipboard.
+pub trait Clipboard {
+    /// Puts `text` on the clipboard.
+    fn copy(&mut self, text: &str) -> io::Result<()>;
+    /// The text on the clipboard, `None` when there is none.
+    fn paste(&mut self) -> io::Result<Option<String>>;
+    /// What went wrong with the last copy that still succeeded, like the
+    /// text not reaching every place it was meant for. Asking clears it.
+    fn take_warning(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// A clipboard used by several editors.
+pub type SharedClipboard = Rc<RefCell<dyn Clipboard>>;
+
+/// Wraps `clipboard` to be shared between editors.
+pub fn shared<C: Clipboard + 'static>(clipboard: C) -> SharedClipboard {
+    Rc::new(RefCell::new(clipboard))
+}
+
+/// Keeps the text in memory, invisible to other programs.
+#[derive(Debug, Default)]
+pub struct InternalClipboard {
+    text: Option<String>,
+}
+
+impl InternalClipboard {
+    pub fn new() -> Self {
+        InternalClipboard::default()
+    }
+}
+
+impl Clipboard for InternalClipboard {
+    fn copy(&mut self, text: &str) -> io::Result<()> {
+        self.text = Some(text.to_string());
+        Ok(())
+    }
+
+    fn paste(&mut self) -> io::Result<Option<String>> {
+        Ok(self.text.clone())
+    }
+}
+
+/// An external program that copies its standard input to the system
+/// clipboard, and another one printing the clipboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tool {
+    pub copy: Vec<String>,
+    pub paste: Vec<String>,
+}
+
+impl Tool {
+    pub fn new(copy: &[&str], paste: &[&str]) -> Self {
+        let owned = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
+        Tool {
+            copy: owned(copy),
+            paste: owned(paste),
+        }
+    }
+
+    /// The tool for the running desktop session, `None` when there is no
+    /// session or nothing is installed for it.
+    pub fn detect() -> Option<Tool> {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+        let x11 = std::env::var_os("DISPLAY").is_some();
+        let candidates = [
+            (
+                wayland,
+                Tool::new(&["wl-copy"], &["wl-paste", "--no-newline"]),
+            ),
+            (
+                x11,
+                Tool::new(
+                    &["xclip", "-selection", "clipboard"],
+                    &["xclip", "-selection", "clipboard", "-o"],
+                ),
+            ),
+            (
+                x11,
+                Tool::new(
+                    &["xsel", "--clipboard", "--input"],
+                    &["xsel", "--clipboard", "--output"],
+                ),
+            ),
+            (
+                cfg!(target_os = "macos"),
+                Tool::new(&["pbcopy"], &["pbpaste"]),
+            ),
+        ];
+        candidates
+            .into_iter()
+            .find(|(usable, tool)| *usable && tool.is_installed())
+            .map(|(_, tool)| tool)
+    }
+
+    fn is_installed(&self) -> bool {
+        let Some(program) = self.copy.first() else {
+            return false;
+        };
+        std::env::var_os("PATH")
+            .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+    }
+
+    fn run_copy(&self, text: &str) -> io::Result<()> {
+        let (program, args) = split(&self.copy)?;
+        // wl-copy stays in the background to serve the clipboard, its output is not read
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let written = match child.stdin.take() {
+            Some(mut stdin) => stdin.write_all(text.as_bytes()),
+            None => Ok(()),
+        };
+        // a tool quitting early breaks the pipe, its exit status tells why
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("{program} failed with {status}")));
+        }
+        written
+    }
+
+    fn run_paste(&self) -> io::Result<String> {
+        let (program, args) = split(&self.paste)?;
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{program} failed with {}",
+                output.status
+            )));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "clipboard is not UTF-8"))
+    }
+}
+
+fn split(command: &[String]) -> io::Result<(&String, &[String])> {
+    command
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty clipboard command"))
+}
+
+/// The desktop clipboard, with a copy kept in memory for when it cannot be
+/// read back.
+pub struct SystemClipboard {
+    internal: InternalClipboard,
+    /// Where the OSC 52 sequence is written, the terminal.
+    terminal: Option<Box<dyn Write>>,
+    tool: Option<Tool>,
+    warning: Option<String>,
+}
+
+impl SystemClipboard {
+    pub fn new(terminal: Option<Box<dyn Write>>, tool: Option<Tool>) -> Self {
+        SystemClipboard {
+            internal: InternalClipboard::new(),
+            terminal,
+            tool,
+            warning: None,
+        }
+    }
+
+    /// Copies through the terminal on standard output and the tool found by
+    /// `Tool::detect`.
+    pub fn detect() -> Self {
+        SystemClipboard::new(Some(Box::new(io::stdout())), Tool::detect())
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn copy(&mut self, text: &str) -> io::Result<()> {
+        self.internal.copy(text)?;
+        // the text can be pasted in the editor now, not reaching the desktop
+        // is only worth a warning
+        let mut failures = Vec::new();
+        if let Some(terminal) = &mut self.terminal {
+            let written = terminal
+                .write_all(osc52(text).as_bytes())
+                .and_then(|()| terminal.flush());
+            if let Err(error) = written {
+                failures.push(format!("terminal: {error}"));
+            }
+        }
+        if let Some(Err(error)) = self.tool.as_ref().map(|tool| tool.run_copy(text)) {
+            failures.push(error.to_string());
+        }
+        self.warning = (!failures.is_empty()).then(|| failures.join(", "));
+        Ok(())
+    }
+
+    fn paste(&mut self) -> io::Result<Option<String>> {
+        // the terminal cannot be asked for its clipboard, only a tool can,
+        // what was copied here is better than nothing when the tool fails
+        match self.tool.as_ref().map(Tool::run_paste) {
+            Some(Ok(text)) => Ok(Some(text)),
+            _ => self.internal.paste(),
+        }
+    }
+
+    fn take_warning(&mut self) -> Option<String> {
+        self.warning.take()
+    }
+}
+
+/// The escape sequence asking the terminal to put `text` on the clipboard.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
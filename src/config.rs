@@ -0,0 +1,124 @@
+//! Settings read from a configuration file.
+//!
+//! The file holds one `key = value` setting per line, blank lines and lines
+//! starting with `#` are skipped:
+//!
+//! ```text
+//! # wrap long lines at word boundaries
+//! wrap = words
+//! line_numbers = relative
+//! # `-` and `_` are part of words besides letters and digits
+//! word_chars = -_
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::editor::Editor;
+use crate::error::{self, EditorError, WithPath};
+use crate::gutter::LineNumbers;
+use crate::motion::WordChars;
+use crate::wrap::Wrap;
+
+/// Settings every opened file starts with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub wrap: Wrap,
+    pub line_numbers: LineNumbers,
+    /// Characters besides letters and digits that word motions keep in a word.
+    pub word_chars: WordChars,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            wrap: Wrap::Off,
+            line_numbers: LineNumbers::Off,
+            word_chars: WordChars::default(),
+        }
+    }
+}
+
+/// Where the configuration is read from unless another file is given,
+/// `None` when there is no home directory.
+pub fn default_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config.join("rust_terminal_notepad").join("config"))
+}
+
+impl Config {
+    /// Reads the configuration in `path`.
+    pub fn load(path: &Path) -> error::Result<Config> {
+        let text = fs::read_to_string(path).with_path(path)?;
+        Config::parse(&text).map_err(|(line, message)| EditorError::Config {
+            path: path.to_path_buf(),
+            line,
+            message,
+        })
+    }
+
+    /// Reads the file at `default_path`, the defaults when there is none.
+    pub fn load_default() -> error::Result<Config> {
+        match default_path() {
+            Some(path) if path.exists() => Config::load(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    /// The settings in `text`, or the line number and the reason of the
+    /// first line that is not understood.
+    pub fn parse(text: &str) -> Result<Config, (usize, String)> {
+        let mut config = Config::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| (number + 1, message);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("expected 'key = value', found '{line}'")))?;
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "wrap" => {
+                    config.wrap = match value {
+                        "off" => Wrap::Off,
+                        "anywhere" => Wrap::Anywhere,
+                        "words" => Wrap::Words,
+                        _ => {
+                            return Err(error(format!(
+                                "wrap is off, anywhere or words, not '{value}'"
+                            )))
+                        }
+                    }
+                }
+                "line_numbers" => {
+                    config.line_numbers = match value {
+                        "off" => LineNumbers::Off,
+                        "absolute" => LineNumbers::Absolute,
+                        "relative" => LineNumbers::Relative,
+                        "hybrid" => LineNumbers::Hybrid,
+                        _ => {
+                            return Err(error(format!(
+                                "line_numbers is off, absolute, relative or hybrid, not '{value}'"
+                            )))
+                        }
+                    }
+                }
+                "word_chars" => config.word_chars = WordChars::new(value),
+                _ => return Err(error(format!("unknown setting '{key}'"))),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Applies the settings to `editor`.
+    pub fn apply(&self, editor: &mut Editor) {
+        editor.set_wrap(self.wrap);
+        editor.set_line_numbers(self.line_numbers);
+        editor.set_word_chars(self.word_chars.clone());
+    }
+}
@@ -0,0 +1,1340 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::buffer::TextBuffer;
+use crate::clipboard::{self, InternalClipboard, SharedClipboard};
+use crate::encoding::Encoding;
+use crate::error::{self, EditorError};
+use crate::gutter::LineNumbers;
+use crate::history::{Edit, Grouping, History};
+use crate::line_ending::LineEnding;
+use crate::motion::{self, WordChars};
+use crate::swap::{self, DiffLine, SwapFile};
+use crate::viewport::Viewport;
+use crate::wrap::{self, Wrap};
+use crate::{
+    initialize_text_buffer_with, line_length, position_to_index, save, total_lines, undo_file,
+    unicode, LoadedText,
+};
+
+/// Unsaved edits after which the swap file is written regardless of time.
+pub const SWAP_EDITS: usize = 50;
+/// Longest time unsaved edits wait before they are journaled.
+pub const SWAP_INTERVAL: Duration = Duration::from_secs(2);
+/// Text size after which both journaling limits above grow once more, the
+/// whole text is rewritten every time.
+pub const SWAP_SCALE: usize = 1024 * 1024;
+/// How long a message stays in the status bar.
+pub const MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Screen size assumed until the driver reports the real one.
+const DEFAULT_SCREEN_SIZE: (u16, u16) = (80, 24);
+
+/// What the terminal driver should do after an event has been handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Keep reading events.
+    Continue,
+    /// Leave the editor, whatever had to be saved is saved already.
+    Quit,
+}
+
+/// A question the editor waits to have answered before it goes on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    /// Quitting with unsaved changes: save them, drop them or stay.
+    SaveChanges,
+    /// A swap file left by a crash: recover it, look at the diff or discard it.
+    Recover,
+    /// Like `Recover`, but the process that wrote the swap file still seems
+    /// to be running.
+    RecoverInUse,
+}
+
+impl Prompt {
+    pub fn question(&self) -> &'static str {
+        match self {
+            Prompt::SaveChanges => "Save changes? (y/n/cancel)",
+            Prompt::Recover => "Unsaved changes found: recover, diff or discard? (r/d/x)",
+            Prompt::RecoverInUse => {
+                "Warning: another editor may have the file open! Recover, diff or discard? (r/d/x)"
+            }
+        }
+    }
+}
+
+/// Editing state: the text buffer, the cursor and the file it belongs to.
+///
+/// The editor knows nothing about the terminal, every key press goes through
+/// `handle_event`, so the binary and the tests drive exactly the same logic.
+pub struct Editor {
+    buffer: TextBuffer,
+    cursor_position: (usize, usize),
+    file_name: String,
+    history: History,
+    prompt: Option<Prompt>,
+    message: Option<String>,
+    message_expires: Option<Instant>,
+    /// Swap file found when opening, until the user decides what to do.
+    recovery: Option<SwapFile>,
+    show_diff: bool,
+    /// Edits not journaled in the swap file yet.
+    unjournaled_edits: usize,
+    /// When the oldest edit not journaled yet has waited long enough.
+    journal_due: Option<Instant>,
+    /// Whether the swap file next to the file is this editor's to remove.
+    owns_swap: bool,
+    viewport: Viewport,
+    /// Width of the whole screen, the gutter and the text share it.
+    screen_width: usize,
+    wrap: Wrap,
+    line_numbers: LineNumbers,
+    line_ending: LineEnding,
+    encoding: Encoding,
+    /// Refuses every change to the text and to the file.
+    read_only: bool,
+    word_chars: WordChars,
+    /// Where the selection started, it reaches from there to the cursor.
+    selection_anchor: Option<(usize, usize)>,
+    clipboard: SharedClipboard,
+    /// Where the undo history is kept between sessions, `None` to forget it
+    /// when the editor is closed.
+    undo_dir: Option<PathBuf>,
+}
+
+impl Editor {
+    /// Creates an empty editor which will save into `file_name`.
+    pub fn new(file_name: &str) -> Self {
+        Editor::from_loaded(file_name, LoadedText::from_text(""))
+    }
+
+    /// Opens `file_name`, loading its content if the file exists.
+    ///
+    /// When a crash left unsaved changes in a swap file the editor starts
+    /// with `Prompt::Recover`, or with `Prompt::RecoverInUse` when the editor
+    /// that wrote it still seems to be running.
+    pub fn open(file_name: &str) -> error::Result<Self> {
+        Editor::open_with_encoding(file_name, Encoding::Utf8)
+    }
+
+    /// Opens `file_name` stored in `encoding`, which it is saved in again.
+    pub fn open_with_encoding(file_name: &str, encoding: Encoding) -> error::Result<Self> {
+        let loaded = initialize_text_buffer_with(file_name, encoding)?;
+        let mut editor = Editor::from_loaded(file_name, loaded);
+        editor.encoding = encoding;
+        Ok(editor)
+    }
+
+    /// Creates an editor holding `text`, not saved in `file_name` yet.
+    pub fn from_text(file_name: &str, text: &str) -> Self {
+        let mut editor = Editor::from_loaded(file_name, LoadedText::from_text(text));
+        if !text.is_empty() {
+            editor.history.mark_unsaved();
+            editor.note_edit();
+        }
+        editor
+    }
+
+    fn from_loaded(file_name: &str, loaded: LoadedText) -> Self {
+        let mut editor = Editor {
+            buffer: loaded.buffer,
+            cursor_position: loaded.cursor,
+            file_name: file_name.to_string(),
+            history: History::new(),
+            prompt: loaded.recovery.as_ref().map(|recovery| {
+                if recovery.is_stale() {
+                    Prompt::Recover
+                } else {
+                    Prompt::RecoverInUse
+                }
+            }),
+            message: None,
+            message_expires: None,
+            recovery: loaded.recovery,
+            show_diff: false,
+            unjournaled_edits: 0,
+            journal_due: None,
+            owns_swap: false,
+            viewport: text_area(DEFAULT_SCREEN_SIZE),
+            screen_width: DEFAULT_SCREEN_SIZE.0 as usize,
+            wrap: Wrap::Off,
+            line_numbers: LineNumbers::Off,
+            line_ending: loaded.line_ending,
+            encoding: Encoding::default(),
+            read_only: false,
+            word_chars: WordChars::default(),
+            selection_anchor: None,
+            clipboard: clipboard::shared(InternalClipboard::new()),
+            undo_dir: None,
+        };
+        if let Some(warning) = loaded.warning {
+            editor.show_message(warning);
+        }
+        editor.scroll_to_cursor();
+        editor
+    }
+
+    pub fn buffer(&self) -> &TextBuffer {
+        &self.buffer
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Copy of the whole text.
+    pub fn text(&self) -> String {
+        self.buffer.to_string()
+    }
+
+    /// Cursor position as (column, line), the column counts grapheme clusters.
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor_position
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Line endings the file is saved with.
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Character encoding the file is saved in.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Whether the text and the file are only looked at.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// What word motions and deletions treat as part of a word.
+    pub fn word_chars(&self) -> &WordChars {
+        &self.word_chars
+    }
+
+    pub fn set_word_chars(&mut self, word_chars: WordChars) {
+        self.word_chars = word_chars;
+    }
+
+    /// Moves the cursor to column `x` of line `y`, both counted from 0 and
+    /// kept inside the text, and drops the selection.
+    pub fn go_to(&mut self, x: usize, y: usize) {
+        self.selection_anchor = None;
+        self.move_to(x, y);
+        self.scroll_to_cursor();
+    }
+
+    /// Start and end of the selected text, in text order, `None` when
+    /// nothing is selected.
+    pub fn selection(&self) -> Option<((usize, usize), (usize, usize))> {
+        let anchor = self.selection_anchor?;
+        let cursor = self.cursor_position;
+        // positions are ordered by line first
+        match (anchor.1, anchor.0).cmp(&(cursor.1, cursor.0)) {
+            std::cmp::Ordering::Less => Some((anchor, cursor)),
+            std::cmp::Ordering::Greater => Some((cursor, anchor)),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// The selected text, `None` when nothing is selected.
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection_range().map(|range| self.buffer.slice(range))
+    }
+
+    /// Selects the whole text, the cursor goes to its end.
+    pub fn select_all(&mut self) {
+        self.history.seal();
+        self.selection_anchor = Some((0, 0));
+        let last = self.last_line();
+        self.cursor_position = (self.line_length(last), last);
+    }
+
+    /// Bytes of the buffer that are selected.
+    fn selection_range(&self) -> Option<std::ops::Range<usize>> {
+        let (start, end) = self.selection()?;
+        Some(self.position_to_index(start)..self.position_to_index(end))
+    }
+
+    /// Uses `clipboard` for cutting, copying and pasting from now on.
+    pub fn set_clipboard(&mut self, clipboard: SharedClipboard) {
+        self.clipboard = clipboard;
+    }
+
+    /// Keeps the undo history in `dir` between sessions from now on. The
+    /// history stored there for the file is picked up if nothing was edited
+    /// yet and it still matches the text.
+    pub fn set_undo_dir(&mut self, dir: Option<PathBuf>) {
+        self.undo_dir = dir;
+        let path = Path::new(&self.file_name);
+        if self.is_modified()
+            || self.history.can_undo()
+            || self.history.can_redo()
+            || !path.exists()
+        {
+            return;
+        }
+        if let Some(history) = self
+            .undo_dir
+            .as_ref()
+            .and_then(|dir| undo_file::load(dir, path, &self.buffer))
+        {
+            self.history = history;
+        }
+    }
+
+    /// Inserts `text` at the cursor, or in place of the selection, as a
+    /// single undo step. Line breaks of any kind become `\n` like in the
+    /// rest of the buffer.
+    pub fn paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if text.is_empty() || self.replace_selection(&text) {
+            return;
+        }
+        let index = self.position_to_index(self.cursor_position);
+        self.insert_text(index, &text, Grouping::Single);
+    }
+
+    /// Whether the buffer has changes that are not saved yet.
+    pub fn is_modified(&self) -> bool {
+        !self.history.is_saved()
+    }
+
+    /// The part of the text on the screen.
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+
+    /// Tells the editor the size of the screen, the bottom row of which is
+    /// left to the status bar.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        let mut viewport = text_area((width, height));
+        // a taller screen shows more of the lines above instead of blank rows,
+        // a wider one scrolls back left when the cursor allows it
+        let line_count = self.buffer.line_count();
+        viewport.top = self
+            .viewport
+            .top
+            .min(line_count.saturating_sub(viewport.height));
+        self.viewport = viewport;
+        self.screen_width = width as usize;
+        self.scroll_to_cursor();
+    }
+
+    /// How lines wider than the screen are shown.
+    pub fn wrap(&self) -> Wrap {
+        self.wrap
+    }
+
+    pub fn set_wrap(&mut self, wrap: Wrap) {
+        self.wrap = wrap;
+        self.viewport.left = 0;
+        self.viewport.top_row = 0;
+        self.scroll_to_cursor();
+    }
+
+    /// Which line numbers are shown in the gutter.
+    pub fn line_numbers(&self) -> LineNumbers {
+        self.line_numbers
+    }
+
+    pub fn set_line_numbers(&mut self, line_numbers: LineNumbers) {
+        self.line_numbers = line_numbers;
+        self.scroll_to_cursor();
+    }
+
+    /// Cells left of the text taken by line numbers.
+    pub fn gutter_width(&self) -> usize {
+        self.line_numbers.width(total_lines(&self.buffer))
+    }
+
+    fn cycle_line_numbers(&mut self) {
+        self.set_line_numbers(self.line_numbers.next());
+        self.show_message(format!("Line numbers: {}", self.line_numbers.name()));
+    }
+
+    /// Rows line `y` is shown in, a single one unless lines wrap.
+    pub fn rows(&self, y: usize) -> Vec<std::ops::Range<usize>> {
+        wrap::rows(&self.buffer.line(y), self.viewport.width, self.wrap)
+    }
+
+    /// Where the cursor is on the screen, right of the gutter.
+    pub fn screen_cursor(&self) -> (usize, usize) {
+        let (x, y) = self.cursor_position;
+        let line = self.buffer.line(y);
+        let gutter = self.gutter_width();
+        if self.wrap == Wrap::Off {
+            let screen_x = unicode::width_before(&line, x);
+            return (
+                gutter + screen_x.saturating_sub(self.viewport.left),
+                y.saturating_sub(self.viewport.top),
+            );
+        }
+        let (row, screen_x) = wrap::locate(&self.rows(y), &line, x);
+        let screen_y = self.viewport.screen_row(y, row, |y| self.rows(y).len());
+        (gutter + screen_x, screen_y)
+    }
+
+    /// Scrolls the viewport as far as needed to show the cursor. The text
+    /// gets what the gutter leaves of the screen, which changes as lines
+    /// are added.
+    fn scroll_to_cursor(&mut self) {
+        self.viewport.width = self.screen_width.saturating_sub(self.gutter_width());
+        let (x, y) = self.cursor_position;
+        let line = self.buffer.line(y);
+        if self.wrap == Wrap::Off {
+            let screen_x = unicode::width_before(&line, x);
+            self.viewport.scroll_to(screen_x, y);
+            return;
+        }
+        let (row, _) = wrap::locate(&self.rows(y), &line, x);
+        let (width, wrap) = (self.viewport.width, self.wrap);
+        let buffer = &self.buffer;
+        self.viewport
+            .scroll_to_row(y, row, |y| wrap::rows(&buffer.line(y), width, wrap).len());
+    }
+
+    fn toggle_wrap(&mut self) {
+        self.set_wrap(match self.wrap {
+            Wrap::Off => Wrap::Words,
+            Wrap::Anywhere | Wrap::Words => Wrap::Off,
+        });
+        let state = if self.wrap == Wrap::Off { "off" } else { "on" };
+        self.show_message(format!("Line wrapping {state}"));
+    }
+
+    /// The question waiting for an answer, if any.
+    pub fn prompt(&self) -> Option<Prompt> {
+        self.prompt
+    }
+
+    /// Text for the message area: the pending question or the last message.
+    pub fn message(&self) -> Option<&str> {
+        match self.prompt {
+            Some(prompt) => Some(prompt.question()),
+            None => self.message.as_deref(),
+        }
+    }
+
+    /// Difference between the file and the swap file, while the user asked
+    /// to see it before deciding.
+    pub fn recovery_diff(&self) -> Option<Vec<DiffLine>> {
+        let recovery = self.recovery.as_ref().filter(|_| self.show_diff)?;
+        Some(swap::diff(&self.buffer.to_string(), &recovery.text))
+    }
+
+    /// Writes the whole buffer to `file_name`, along with the undo history.
+    ///
+    /// The file is replaced atomically, if anything goes wrong the previous
+    /// content stays on disk.
+    pub fn save(&mut self) -> error::Result<()> {
+        if self.read_only {
+            return Err(EditorError::ReadOnly(self.file_name.clone().into()));
+        }
+        let line_ending = self.line_ending;
+        let encoding = self.encoding;
+        save::write_atomically(Path::new(&self.file_name), |out| {
+            for chunk in self.buffer.chunks() {
+                let chunk = match line_ending {
+                    LineEnding::Lf => chunk.to_string(),
+                    LineEnding::CrLf => chunk.replace('\n', "\r\n"),
+                };
+                let bytes = encoding.encode(&chunk).map_err(|c| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("'{c}' cannot be written in {}", encoding.name()),
+                    )
+                })?;
+                out.write_all(&bytes)?;
+            }
+            Ok(())
+        })?;
+
+        // the text is safe at this point, losing the history only costs undo steps
+        if let Some(dir) = &self.undo_dir {
+            let _ = undo_file::store(dir, Path::new(&self.file_name), &self.buffer, &self.history);
+        }
+        self.history.mark_saved();
+        self.unjournaled_edits = 0;
+        self.journal_due = None;
+        self.remove_swap();
+        Ok(())
+    }
+
+    /// Journals the buffer in the swap file next to `file_name`.
+    pub fn write_swap(&mut self) -> error::Result<()> {
+        swap::write(Path::new(&self.file_name), &self.buffer)?;
+        self.owns_swap = true;
+        self.unjournaled_edits = 0;
+        self.journal_due = None;
+        Ok(())
+    }
+
+    /// Saves unsaved changes in the swap file after a crash, so the next
+    /// `open` offers to recover them. Returns where they went, `None` when
+    /// there was nothing to save.
+    pub fn emergency_dump(&mut self) -> error::Result<Option<PathBuf>> {
+        if !self.is_modified() {
+            return Ok(None);
+        }
+        self.write_swap()?;
+        Ok(Some(swap::swap_path(Path::new(&self.file_name))))
+    }
+
+    /// Writes the swap file once enough edits piled up or enough time passed.
+    fn journal(&mut self, now: Instant) {
+        let due = self.journal_due.is_some_and(|due| now >= due);
+        let edits = SWAP_EDITS * self.journal_scale();
+        if self.unjournaled_edits == 0 || (self.unjournaled_edits < edits && !due) {
+            return;
+        }
+        if let Err(error) = self.write_swap() {
+            // try again later instead of on every tick
+            self.journal_due = Some(now + self.journal_interval());
+            self.show_message(format!("Cannot write swap file: {error}"));
+        }
+    }
+
+    // a swap file of another editor still running is left alone
+    fn remove_swap(&mut self) {
+        if self.owns_swap && swap::remove(Path::new(&self.file_name)).is_ok() {
+            self.owns_swap = false;
+        }
+    }
+
+    /// Shows `message` in the status bar until the next key or until it
+    /// expires.
+    pub fn show_message(&mut self, message: String) {
+        self.message = Some(message);
+        self.message_expires = Some(Instant::now() + MESSAGE_TIMEOUT);
+    }
+
+    /// Starts the timer for journaling the changed buffer.
+    fn note_edit(&mut self) {
+        self.unjournaled_edits += 1;
+        let interval = self.journal_interval();
+        self.journal_due
+            .get_or_insert_with(|| Instant::now() + interval);
+    }
+
+    /// How many times the journaling limits are stretched for the text.
+    fn journal_scale(&self) -> usize {
+        1 + self.buffer.len() / SWAP_SCALE
+    }
+
+    fn journal_interval(&self) -> Duration {
+        SWAP_INTERVAL * self.journal_scale() as u32
+    }
+
+    /// Runs whatever timers are due at `now`: journaling unsaved edits and
+    /// expiring the message.
+    pub fn tick(&mut self, now: Instant) {
+        self.journal(now);
+        if self.message_expires.is_some_and(|expires| now >= expires) {
+            self.message = None;
+            self.message_expires = None;
+        }
+    }
+
+    /// The earliest time `tick` has something to do, `None` while no timer
+    /// is running.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        [self.journal_due, self.message_expires]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Saves and reports the outcome in the status bar.
+    fn save_with_message(&mut self) -> bool {
+        match self.save() {
+            Ok(()) => {
+                let lines = total_lines(&self.buffer);
+                let noun = if lines == 1 { "line" } else { "lines" };
+                self.show_message(format!("Saved {lines} {noun}"));
+                true
+            }
+            Err(error) => {
+                self.show_message(format!("Cannot save: {error}"));
+                false
+            }
+        }
+    }
+
+    /// Quits once the buffer is saved, stays open when saving failed.
+    fn save_and_quit(&mut self) -> Action {
+        // a read-only file has nothing to save, unless it was never saved
+        if (self.read_only && !self.is_modified()) || self.save_with_message() {
+            Action::Quit
+        } else {
+            Action::Continue
+        }
+    }
+
+    /// Applies a single terminal event to the editor state.
+    pub fn handle_event(&mut self, event: Event) -> Action {
+        match event {
+            // skip releasing the button
+            Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+                let action = self.handle_key(key_event);
+                if action == Action::Quit {
+                    // whatever was worth keeping is saved or deliberately dropped
+                    self.remove_swap();
+                } else {
+                    self.journal(Instant::now());
+                    self.scroll_to_cursor();
+                }
+                action
+            }
+            // with bracketed paste the whole block arrives at once, and is
+            // inserted as one edit instead of a key press per character
+            Event::Paste(text) => {
+                self.message = None;
+                self.message_expires = None;
+                if self.prompt.is_none() {
+                    self.paste(&text);
+                    self.journal(Instant::now());
+                    self.scroll_to_cursor();
+                }
+                Action::Continue
+            }
+            Event::Resize(width, height) => {
+                self.resize(width, height);
+                Action::Continue
+            }
+            _ => Action::Continue,
+        }
+    }
+
+    fn handle_key(&mut self, key_event: KeyEvent) -> Action {
+        // messages stay until the next key press
+        self.message = None;
+        self.message_expires = None;
+        if let Some(prompt) = self.prompt {
+            return self.answer_prompt(prompt, key_event);
+        }
+
+        let mut key_event = key_event;
+        if is_motion(&key_event) {
+            // Shift extends the selection with any motion, without it the
+            // motion drops the selection
+            if key_event.modifiers.contains(KeyModifiers::SHIFT) {
+                self.selection_anchor.get_or_insert(self.cursor_position);
+                key_event.modifiers.remove(KeyModifiers::SHIFT);
+                if let KeyCode::Char(c) = key_event.code {
+                    key_event.code = KeyCode::Char(c.to_ascii_lowercase());
+                }
+            } else {
+                self.selection_anchor = None;
+            }
+        }
+
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Char('q'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => return self.save_and_quit(),
+
+            KeyEvent {
+                code: KeyCode::Char('x'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.cut(),
+
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                self.copy_selection();
+            }
+
+            KeyEvent {
+                code: KeyCode::Char('v'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.paste_clipboard(),
+
+            KeyEvent {
+                code: KeyCode::Char('s'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                self.save_with_message();
+            }
+
+            KeyEvent {
+                code: KeyCode::Char('z'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.undo(),
+
+            KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.redo(),
+
+            KeyEvent {
+                code: KeyCode::Char('a'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.select_all(),
+
+            KeyEvent {
+                code: KeyCode::Char('z'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            } => self.toggle_wrap(),
+
+            KeyEvent {
+                code: KeyCode::Char('l'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            } => self.cycle_line_numbers(),
+
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                ..
+            } => self.insert_char(c),
+
+            // most terminals send Ctrl+Backspace as Ctrl+H
+            KeyEvent {
+                code: KeyCode::Backspace,
+                modifiers: KeyModifiers::CONTROL | KeyModifiers::ALT,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('h'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.delete_word(false),
+
+            KeyEvent {
+                code: KeyCode::Delete,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.delete_word(true),
+
+            KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            } => self.backspace(),
+
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => self.insert_newline(),
+
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } if self.selection_anchor.is_some() => self.selection_anchor = None,
+
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => {
+                if !self.is_modified() {
+                    return Action::Quit;
+                }
+                self.prompt = Some(Prompt::SaveChanges);
+            }
+
+            KeyEvent {
+                code: KeyCode::Left,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_word(false),
+
+            KeyEvent {
+                code: KeyCode::Right,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_word(true),
+
+            KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_paragraph(false),
+
+            KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_paragraph(true),
+
+            KeyEvent {
+                code: KeyCode::Char('a'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            } => self.move_sentence(false),
+
+            KeyEvent {
+                code: KeyCode::Char('e'),
+                modifiers: KeyModifiers::ALT,
+                ..
+            } => self.move_sentence(true),
+
+            KeyEvent {
+                code: KeyCode::Left,
+                ..
+            } => self.move_left(),
+
+            KeyEvent {
+                code: KeyCode::Right,
+                ..
+            } => self.move_right(),
+
+            KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => self.move_up(),
+
+            KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => self.move_down(),
+
+            KeyEvent {
+                code: KeyCode::Home,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => self.move_to_column(0),
+
+            KeyEvent {
+                code: KeyCode::End,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => self.move_to_column(self.line_length(self.cursor_position.1)),
+
+            KeyEvent {
+                code: KeyCode::Home,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_to(0, 0),
+
+            KeyEvent {
+                code: KeyCode::End,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_to(usize::MAX, usize::MAX),
+
+            KeyEvent {
+                code: KeyCode::PageUp,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => self.move_page(false),
+
+            KeyEvent {
+                code: KeyCode::PageDown,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => self.move_page(true),
+
+            KeyEvent {
+                code: KeyCode::Delete,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => self.delete(),
+
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    /// Number of characters in line `y`.
+    fn line_length(&self, y: usize) -> usize {
+        line_length(&self.buffer, y)
+    }
+
+    fn last_line(&self) -> usize {
+        total_lines(&self.buffer) - 1
+    }
+
+    /// Byte offset of the cursor position (x, y) in the buffer.
+    fn position_to_index(&self, cursor: (usize, usize)) -> usize {
+        position_to_index(&self.buffer, cursor)
+    }
+
+    fn answer_prompt(&mut self, prompt: Prompt, key_event: KeyEvent) -> Action {
+        let KeyCode::Char(answer) = key_event.code else {
+            if key_event.code == KeyCode::Esc {
+                self.prompt = None;
+            }
+            return Action::Continue;
+        };
+        // Ctrl+X cutting or Ctrl+Y redoing by habit must not answer
+        if !matches!(
+            key_event.modifiers,
+            KeyModifiers::NONE | KeyModifiers::SHIFT
+        ) {
+            return Action::Continue;
+        }
+        match (prompt, answer.to_ascii_lowercase()) {
+            (Prompt::SaveChanges, 'y') => {
+                self.prompt = None;
+                return self.save_and_quit();
+            }
+            (Prompt::SaveChanges, 'n') => return Action::Quit,
+            (Prompt::SaveChanges, 'c') => self.prompt = None,
+            (Prompt::Recover | Prompt::RecoverInUse, 'r') => {
+                self.prompt = None;
+                self.recover();
+            }
+            (Prompt::Recover | Prompt::RecoverInUse, 'd') => self.show_diff = !self.show_diff,
+            (Prompt::Recover | Prompt::RecoverInUse, 'x') => {
+                self.prompt = None;
+                self.discard_recovery();
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    /// Replaces the text with the one from the swap file, as a single undo
+    /// step, and claims the swap file right away.
+    fn recover(&mut self) {
+        if !self.writable() {
+            return;
+        }
+        let Some(recovery) = self.recovery.take() else {
+            return;
+        };
+        self.show_diff = false;
+        let cursor_before = self.cursor_position;
+        self.selection_anchor = None;
+        let mut edits = Vec::new();
+        if !self.buffer.is_empty() {
+            let text = self.buffer.to_string();
+            self.buffer.remove(0..text.len());
+            edits.push(Edit::Delete { offset: 0, text });
+        }
+        if !recovery.text.is_empty() {
+            self.buffer.insert(0, &recovery.text);
+            edits.push(Edit::Insert {
+                offset: 0,
+                text: recovery.text,
+            });
+        }
+        self.cursor_position = self.offset_to_position(self.buffer.len());
+        self.history
+            .record_step(edits, cursor_before, self.cursor_position);
+        self.note_edit();
+        if let Err(error) = self.write_swap() {
+            self.show_message(format!("Cannot write swap file: {error}"));
+        }
+    }
+
+    fn discard_recovery(&mut self) {
+        let Some(recovery) = self.recovery.take() else {
+            return;
+        };
+        self.show_diff = false;
+        // the journal of another editor still running is not ours to drop
+        if !recovery.is_stale() {
+            return;
+        }
+        if let Err(error) = swap::remove(Path::new(&self.file_name)) {
+            self.show_message(format!("Cannot remove swap file: {error}"));
+        }
+    }
+
+    /// Cursor position (x, y) of byte `offset` in the buffer.
+    fn offset_to_position(&self, offset: usize) -> (usize, usize) {
+        let y = self.buffer.line_of(offset);
+        let line = self.buffer.line(y);
+        (
+            unicode::column_at_byte(&line, offset - self.buffer.line_start(y)),
+            y,
+        )
+    }
+
+    /// Whether the text may be changed, says why not in the status bar.
+    fn writable(&mut self) -> bool {
+        if self.read_only {
+            self.show_message(format!("{} is opened read-only", self.file_name));
+        }
+        !self.read_only
+    }
+
+    /// Inserts `text` at byte `index`, records it for undo and moves the
+    /// cursor past it.
+    fn insert_text(&mut self, index: usize, text: &str, grouping: Grouping) {
+        if !self.writable() {
+            return;
+        }
+        let cursor_before = self.cursor_position;
+        self.selection_anchor = None;
+        self.buffer.insert(index, text);
+        self.note_edit();
+        // a combining mark joins the grapheme before it, so the column comes from the offset
+        self.cursor_position = self.offset_to_position(index + text.len());
+        let edit = Edit::Insert {
+            offset: index,
+            text: text.to_string(),
+        };
+        self.history
+            .record(edit, grouping, cursor_before, self.cursor_position);
+    }
+
+    /// Removes the bytes in `range`, records it for undo and moves the
+    /// cursor to where they were.
+    fn remove_text(&mut self, range: std::ops::Range<usize>, grouping: Grouping) {
+        if !self.writable() {
+            return;
+        }
+        let cursor_before = self.cursor_position;
+        self.selection_anchor = None;
+        let text = self.buffer.slice(range.clone());
+        self.buffer.remove(range.clone());
+        self.note_edit();
+        self.cursor_position = self.offset_to_position(range.start);
+        let edit = Edit::Delete {
+            offset: range.start,
+            text,
+        };
+        self.history
+            .record(edit, grouping, cursor_before, self.cursor_position);
+    }
+
+    /// Replaces the selected text with `text` as a single undo step, `false`
+    /// when nothing is selected.
+    fn replace_selection(&mut self, text: &str) -> bool {
+        let Some(range) = self.selection_range() else {
+            return false;
+        };
+        if !self.writable() {
+            return true;
+        }
+        let cursor_before = self.cursor_position;
+        self.selection_anchor = None;
+        let mut edits = vec![Edit::Delete {
+            offset: range.start,
+            text: self.buffer.slice(range.clone()),
+        }];
+        self.buffer.remove(range.clone());
+        if !text.is_empty() {
+            self.buffer.insert(range.start, text);
+            edits.push(Edit::Insert {
+                offset: range.start,
+                text: text.to_string(),
+            });
+        }
+        self.cursor_position = self.offset_to_position(range.start + text.len());
+        self.history
+            .record_step(edits, cursor_before, self.cursor_position);
+        self.note_edit();
+        true
+    }
+
+    /// Puts the selected text on the clipboard, `false` when nothing is
+    /// selected or the clipboard refused it.
+    fn copy_selection(&mut self) -> bool {
+        let Some(text) = self.selected_text() else {
+            self.show_message("Nothing is selected".to_string());
+            return false;
+        };
+        let copied = self.clipboard.borrow_mut().copy(&text);
+        match copied {
+            Ok(()) => {
+                let warning = self.clipboard.borrow_mut().take_warning();
+                if let Some(warning) = warning {
+                    let message = format!("Copied, but not to the system clipboard: {warning}");
+                    self.show_message(message);
+                }
+                true
+            }
+            Err(error) => {
+                self.show_message(format!("Cannot copy: {error}"));
+                false
+            }
+        }
+    }
+
+    /// Copies the selection and deletes it once it is on the clipboard.
+    fn cut(&mut self) {
+        if self.copy_selection() {
+            self.replace_selection("");
+        }
+    }
+
+    fn paste_clipboard(&mut self) {
+        let pasted = self.clipboard.borrow_mut().paste();
+        match pasted {
+            Ok(Some(text)) => self.paste(&text),
+            Ok(None) => self.show_message("The clipboard is empty".to_string()),
+            Err(error) => self.show_message(format!("Cannot paste: {error}")),
+        }
+    }
+
+    fn insert_char(&mut self, c: char) {
+        let text = c.encode_utf8(&mut [0; 4]).to_string();
+        if !self.replace_selection(&text) {
+            let index = self.position_to_index(self.cursor_position);
+            self.insert_text(index, &text, Grouping::Typing);
+        }
+    }
+
+    fn insert_newline(&mut self) {
+        if !self.replace_selection("\n") {
+            let index = self.position_to_index(self.cursor_position);
+            self.insert_text(index, "\n", Grouping::Single);
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.replace_selection("") {
+            return;
+        }
+        let (x, y) = self.cursor_position;
+        if (x, y) == (0, 0) {
+            return;
+        }
+        // the previous grapheme, or the line break before the line
+        let start = if x > 0 {
+            self.position_to_index((x - 1, y))
+        } else {
+            self.position_to_index((self.line_length(y - 1), y - 1))
+        };
+        let end = self.position_to_index((x, y));
+        self.remove_text(start..end, Grouping::Deleting);
+    }
+
+    fn delete(&mut self) {
+        if self.replace_selection("") {
+            return;
+        }
+        let (x, y) = self.cursor_position;
+        // the next grapheme, or the line break after the line
+        let end = if x < self.line_length(y) {
+            self.position_to_index((x + 1, y))
+        } else if y < self.last_line() {
+            self.position_to_index((0, y + 1))
+        } else {
+            return;
+        };
+        let start = self.position_to_index((x, y));
+        self.remove_text(start..end, Grouping::Deleting);
+    }
+
+    /// Deletes from the cursor to the start of the word before it, or to the
+    /// start of the next word.
+    fn delete_word(&mut self, forward: bool) {
+        if self.replace_selection("") {
+            return;
+        }
+        let cursor = self.cursor_position;
+        let (start, end) = if forward {
+            let end = motion::word_right(&self.buffer, cursor, &self.word_chars);
+            (cursor, end)
+        } else {
+            let start = motion::word_left(&self.buffer, cursor, &self.word_chars);
+            (start, cursor)
+        };
+        let range = self.position_to_index(start)..self.position_to_index(end);
+        if !range.is_empty() {
+            self.remove_text(range, Grouping::Deleting);
+        }
+    }
+
+    fn undo(&mut self) {
+        if !self.writable() {
+            return;
+        }
+        self.selection_anchor = None;
+        if let Some(cursor) = self.history.undo(&mut self.buffer) {
+            self.cursor_position = cursor;
+            self.note_edit();
+        }
+    }
+
+    fn redo(&mut self) {
+        if !self.writable() {
+            return;
+        }
+        self.selection_anchor = None;
+        if let Some(cursor) = self.history.redo(&mut self.buffer) {
+            self.cursor_position = cursor;
+            self.note_edit();
+        }
+    }
+
+    fn move_left(&mut self) {
+        self.history.seal();
+        if self.cursor_position.0 > 0 {
+            self.cursor_position.0 -= 1;
+        } else if self.cursor_position.1 > 0 {
+            self.cursor_position.1 -= 1;
+            // Set cursor to the end of the previous line
+            self.cursor_position.0 = self.line_length(self.cursor_position.1);
+        }
+    }
+
+    fn move_right(&mut self) {
+        self.history.seal();
+        if self.cursor_position.0 < self.line_length(self.cursor_position.1) {
+            self.cursor_position.0 += 1;
+        } else if self.cursor_position.1 < self.last_line() {
+            self.cursor_position.1 += 1;
+            self.cursor_position.0 = 0;
+        }
+    }
+
+    fn move_up(&mut self) {
+        self.history.seal();
+        if self.wrap != Wrap::Off {
+            self.move_by_row(false);
+        } else if self.cursor_position.1 > 0 {
+            self.move_to_line(self.cursor_position.1 - 1);
+        }
+    }
+
+    fn move_down(&mut self) {
+        self.history.seal();
+        if self.wrap != Wrap::Off {
+            self.move_by_row(true);
+        } else if self.cursor_position.1 < self.last_line() {
+            self.move_to_line(self.cursor_position.1 + 1);
+        }
+    }
+
+    fn move_word(&mut self, forward: bool) {
+        self.history.seal();
+        let cursor = self.cursor_position;
+        self.cursor_position = if forward {
+            motion::word_right(&self.buffer, cursor, &self.word_chars)
+        } else {
+            motion::word_left(&self.buffer, cursor, &self.word_chars)
+        };
+    }
+
+    fn move_sentence(&mut self, forward: bool) {
+        self.history.seal();
+        let cursor = self.cursor_position;
+        self.cursor_position = if forward {
+            motion::sentence_right(&self.buffer, cursor)
+        } else {
+            motion::sentence_left(&self.buffer, cursor)
+        };
+    }
+
+    fn move_paragraph(&mut self, down: bool) {
+        self.history.seal();
+        let y = self.cursor_position.1;
+        self.cursor_position = if down {
+            motion::paragraph_down(&self.buffer, y)
+        } else {
+            motion::paragraph_up(&self.buffer, y)
+        };
+    }
+
+    /// Like `go_to`, but a selection being made goes on.
+    fn move_to(&mut self, x: usize, y: usize) {
+        self.history.seal();
+        let y = y.min(self.last_line());
+        self.cursor_position = (x.min(self.line_length(y)), y);
+    }
+
+    fn move_to_column(&mut self, x: usize) {
+        self.history.seal();
+        self.cursor_position.0 = x;
+    }
+
+    /// Moves a screen height down or up. Without wrapping the text scrolls
+    /// by as many lines, so the cursor stays on the same screen row until
+    /// the start or the end of the text is reached.
+    fn move_page(&mut self, down: bool) {
+        self.history.seal();
+        let page = self.viewport.height.max(1);
+        if self.wrap != Wrap::Off {
+            for _ in 0..page {
+                self.move_by_row(down);
+            }
+            return;
+        }
+        let (_, y) = self.cursor_position;
+        let last_top = total_lines(&self.buffer).saturating_sub(page);
+        let (top, target) = if down {
+            (self.viewport.top + page, (y + page).min(self.last_line()))
+        } else {
+            (
+                self.viewport.top.saturating_sub(page),
+                y.saturating_sub(page),
+            )
+        };
+        self.viewport.top = top.min(last_top);
+        self.move_to_line(target);
+    }
+
+    /// Moves one screen row down or up when lines wrap, keeping the screen
+    /// column like `move_to_line` does.
+    fn move_by_row(&mut self, down: bool) {
+        let (x, y) = self.cursor_position;
+        let rows = self.rows(y);
+        let (row, screen_x) = wrap::locate(&rows, &self.buffer.line(y), x);
+        let (target_y, target_row) = match down {
+            true if row + 1 < rows.len() => (y, row + 1),
+            true if y < self.last_line() => (y + 1, 0),
+            false if row > 0 => (y, row - 1),
+            false if y > 0 => (y - 1, self.rows(y - 1).len() - 1),
+            _ => return,
+        };
+        let target_rows = self.rows(target_y);
+        let line = self.buffer.line(target_y);
+        let column = wrap::column_at(&target_rows, &line, target_row, screen_x);
+        self.cursor_position = (column, target_y);
+    }
+
+    /// Moves to line `y` keeping the cursor in the same screen column,
+    /// which differs from the grapheme column around wide characters.
+    fn move_to_line(&mut self, y: usize) {
+        let (x, current) = self.cursor_position;
+        let width = unicode::width_before(&self.buffer.line(current), x);
+        self.cursor_position = (unicode::column_at_width(&self.buffer.line(y), width), y);
+    }
+}
+
+/// Whether `key_event` only moves the cursor, which extends the selection
+/// when Shift is held.
+fn is_motion(key_event: &KeyEvent) -> bool {
+    match key_event.code {
+        KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Home
+        | KeyCode::End
+        | KeyCode::PageUp
+        | KeyCode::PageDown => true,
+        // sentence motions
+        KeyCode::Char(c) => {
+            key_event.modifiers.contains(KeyModifiers::ALT)
+                && matches!(c.to_ascii_lowercase(), 'a' | 'e')
+        }
+        _ => false,
+    }
+}
+
+/// Viewport for a screen of `size`, without the status bar.
+fn text_area((width, height): (u16, u16)) -> Viewport {
+    Viewport::new(width as usize, (height as usize).saturating_sub(1))
+}
@@ -0,0 +1,54 @@
+//! Character encodings files can be read and written in.
+//!
+//! The buffer always holds UTF-8. A file in another encoding is decoded when
+//! it is loaded and encoded again when it is saved.
+
+/// How the text of a file is stored on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// ISO-8859-1, every byte is the character with the same code point.
+    Latin1,
+}
+
+impl Encoding {
+    /// The encoding called `name`, ignoring case, `None` when it is not
+    /// supported.
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" => Some(Encoding::Latin1),
+            _ => None,
+        }
+    }
+
+    /// Name shown in the status bar.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    /// The text stored in `bytes`, `None` when they are not valid in this
+    /// encoding.
+    pub fn decode(&self, bytes: Vec<u8>) -> Option<String> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes).ok(),
+            Encoding::Latin1 => Some(bytes.into_iter().map(char::from).collect()),
+        }
+    }
+
+    /// `text` as it is stored on disk, or the first character this encoding
+    /// cannot store.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, char> {
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(c).map_err(|_| c))
+                .collect(),
+        }
+    }
+}
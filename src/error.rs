@@ -0,0 +1,159 @@
+//! Errors of the library.
+//!
+//! Every failure to load or save a file says which file it was about and,
+//! where it can be told, why: the messages are meant for the status bar and
+//! read fine without the `Debug` output.
+
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Result of the fallible functions of the library.
+pub type Result<T> = std::result::Result<T, EditorError>;
+
+/// What went wrong while loading, saving or journaling a file.
+#[derive(Debug)]
+pub enum EditorError {
+    /// The file, or the directory it should be in, does not exist.
+    NotFound(PathBuf),
+    PermissionDenied(PathBuf),
+    /// The file is not text in the encoding it is read with.
+    InvalidEncoding(PathBuf),
+    /// The file is larger than `MAX_FILE_SIZE` bytes.
+    TooLarge {
+        path: PathBuf,
+        size: u64,
+    },
+    /// The file is opened read-only and cannot be saved.
+    ReadOnly(PathBuf),
+    /// Another editor, still running, has unsaved changes of the file.
+    Locked {
+        path: PathBuf,
+        pid: u32,
+    },
+    /// Line `line` of the configuration file `path` is not understood.
+    Config {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// Any other failure to read or write `path`.
+    File {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A failure not about a file, like the terminal going away.
+    Io(std::io::Error),
+}
+
+/// Largest file the editor loads, the whole text is kept in memory.
+pub const MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+impl EditorError {
+    /// Tells what `error`, returned while working on `path`, means.
+    pub fn from_io(path: &Path, error: std::io::Error) -> Self {
+        let path = path.to_path_buf();
+        match error.kind() {
+            ErrorKind::NotFound => EditorError::NotFound(path),
+            ErrorKind::PermissionDenied => EditorError::PermissionDenied(path),
+            _ => EditorError::File {
+                path,
+                source: error,
+            },
+        }
+    }
+
+    /// The file the error is about, `None` when it is not about a file.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            EditorError::NotFound(path)
+            | EditorError::PermissionDenied(path)
+            | EditorError::InvalidEncoding(path)
+            | EditorError::TooLarge { path, .. }
+            | EditorError::ReadOnly(path)
+            | EditorError::Locked { path, .. }
+            | EditorError::Config { path, .. }
+            | EditorError::File { path, .. } => Some(path),
+            EditorError::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for EditorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditorError::NotFound(path) => {
+                write!(f, "{}: no such file or directory", path.display())
+            }
+            EditorError::PermissionDenied(path) => {
+                write!(f, "{}: permission denied", path.display())
+            }
+            EditorError::InvalidEncoding(path) => {
+                write!(f, "{} is not valid UTF-8 text", path.display())
+            }
+            EditorError::TooLarge { path, size } => write!(
+                f,
+                "{} is too large ({} MiB, at most {} MiB can be edited)",
+                path.display(),
+                size.div_ceil(1024 * 1024),
+                MAX_FILE_SIZE / (1024 * 1024)
+            ),
+            EditorError::ReadOnly(path) => write!(f, "{} is opened read-only", path.display()),
+            EditorError::Locked { path, pid } => write!(
+                f,
+                "{} is being edited in another editor (process {pid})",
+                path.display()
+            ),
+            EditorError::Config {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+            EditorError::File { path, source } => write!(f, "{}: {source}", path.display()),
+            EditorError::Io(source) => source.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EditorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EditorError::File { source, .. } | EditorError::Io(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for EditorError {
+    fn from(error: std::io::Error) -> Self {
+        EditorError::Io(error)
+    }
+}
+
+// lets callers working with io::Result use `?` on the library
+impl From<EditorError> for std::io::Error {
+    fn from(error: EditorError) -> Self {
+        let kind = match &error {
+            EditorError::NotFound(_) => ErrorKind::NotFound,
+            EditorError::PermissionDenied(_) => ErrorKind::PermissionDenied,
+            EditorError::InvalidEncoding(_) => ErrorKind::InvalidData,
+            EditorError::TooLarge { .. } => ErrorKind::FileTooLarge,
+            EditorError::ReadOnly(_) => ErrorKind::ReadOnlyFilesystem,
+            EditorError::Locked { .. } => ErrorKind::ResourceBusy,
+            EditorError::Config { .. } => ErrorKind::InvalidData,
+            EditorError::File { source, .. } | EditorError::Io(source) => source.kind(),
+        };
+        std::io::Error::new(kind, error)
+    }
+}
+
+/// Attaches the path an `io::Result` is about.
+pub(crate) trait WithPath<T> {
+    fn with_path(self, path: &Path) -> Result<T>;
+}
+
+impl<T> WithPath<T> for std::io::Result<T> {
+    fn with_path(self, path: &Path) -> Result<T> {
+        self.map_err(|error| EditorError::from_io(path, error))
+    }
+}
@@ -0,0 +1,54 @@
+//! The loop connecting an editor to its input and its screen.
+//!
+//! Everything runs on one thread. The loop sleeps in `EventSource::poll`
+//! until the next event or the next timer, whichever comes first, so the
+//! cursor blinks and the editor's timers fire while no key is pressed.
+
+use std::time::{Duration, Instant};
+
+use crate::backend::{Backend, EventSource};
+use crate::buffers::Buffers;
+use crate::editor::Action;
+use crate::error;
+use crate::render::Renderer;
+
+/// Time between two changes of the blinking cursor.
+pub const BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Handles events and draws the editor on the screen until the last one
+/// quits or the input closes.
+pub fn run<E, B>(buffers: &mut Buffers, events: &mut E, backend: &mut B) -> error::Result<()>
+where
+    E: EventSource,
+    B: Backend,
+{
+    let mut renderer = Renderer::new();
+    let mut show_cursor = true;
+    let mut next_blink = Instant::now() + BLINK_INTERVAL;
+
+    loop {
+        renderer.draw(buffers.current(), backend, show_cursor)?;
+
+        let deadline = buffers
+            .next_deadline()
+            .map_or(next_blink, |deadline| deadline.min(next_blink));
+        if events.poll(deadline.saturating_duration_since(Instant::now()))? {
+            let Some(event) = events.read_event()? else {
+                return Ok(());
+            };
+            if buffers.handle_event(event) == Action::Quit {
+                return Ok(());
+            }
+            // the cursor stays visible while typing
+            show_cursor = true;
+            next_blink = Instant::now() + BLINK_INTERVAL;
+        }
+
+        let now = Instant::now();
+        if now >= next_blink {
+            show_cursor = !show_cursor;
+            next_blink = now + BLINK_INTERVAL;
+        }
+        buffers.tick(now);
+    }
+}
@@ -0,0 +1,58 @@
+//! Line numbers shown left of the text.
+
+/// Which numbers the gutter shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineNumbers {
+    /// No gutter at all.
+    #[default]
+    Off,
+    /// Every line shows its own number.
+    Absolute,
+    /// Every line shows how far it is from the cursor line, which shows 0.
+    Relative,
+    /// Relative numbers, except for the cursor line which shows its own.
+    Hybrid,
+}
+
+impl LineNumbers {
+    /// The mode after this one when cycling through them.
+    pub fn next(self) -> LineNumbers {
+        match self {
+            LineNumbers::Off => LineNumbers::Absolute,
+            LineNumbers::Absolute => LineNumbers::Relative,
+            LineNumbers::Relative => LineNumbers::Hybrid,
+            LineNumbers::Hybrid => LineNumbers::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LineNumbers::Off => "off",
+            LineNumbers::Absolute => "absolute",
+            LineNumbers::Relative => "relative",
+            LineNumbers::Hybrid => "hybrid",
+        }
+    }
+
+    /// Cells taken by the gutter of a text with `total_lines` lines: the
+    /// digits of the largest number and a space before the text.
+    pub fn width(self, total_lines: usize) -> usize {
+        match self {
+            LineNumbers::Off => 0,
+            _ => total_lines.max(1).to_string().len() + 1,
+        }
+    }
+
+    /// What the gutter shows next to line `y` with the cursor on line
+    /// `cursor_y`, `width` cells wide.
+    pub fn label(self, y: usize, cursor_y: usize, width: usize) -> String {
+        let number = match self {
+            LineNumbers::Off => return String::new(),
+            LineNumbers::Absolute => y + 1,
+            LineNumbers::Relative => y.abs_diff(cursor_y),
+            LineNumbers::Hybrid if y == cursor_y => y + 1,
+            LineNumbers::Hybrid => y.abs_diff(cursor_y),
+        };
+        format!("{number:>digits$} ", digits = width.saturating_sub(1))
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crossterm::event::Event;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::backend::{Backend, EventSource};
+use crate::unicode;
+
+/// Replays a fixed list of events, then reports the input as closed.
+pub struct ScriptedEvents {
+    events: VecDeque<Event>,
+}
+
+impl ScriptedEvents {
+    pub fn new(events: Vec<Event>) -> Self {
+        ScriptedEvents {
+            events: events.into(),
+        }
+    }
+
+    /// Queues one more event after the ones already scripted.
+    pub fn push(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    // the next event, or the end of the script, is always there
+    fn poll(&mut self, _timeout: Duration) -> std::io::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_event(&mut self) -> std::io::Result<Option<Event>> {
+        Ok(self.events.pop_front())
+    }
+}
+
+/// One character cell of the virtual screen, the cell after a wide
+/// character has an empty symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: String,
+    /// Printed in reverse video.
+    pub reversed: bool,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            symbol: " ".to_string(),
+            reversed: false,
+        }
+    }
+}
+
+/// In-memory screen which keeps the grid of cells that would be visible on
+/// a real terminal, so tests can assert on what the user actually sees.
+pub struct HeadlessBackend {
+    width: u16,
+    height: u16,
+    cells: Vec<Vec<Cell>>,
+    cursor: (u16, u16),
+    cursor_visible: bool,
+    reversed: bool,
+    /// Everything printed since the last `take_printed`, with its position.
+    printed: Vec<(u16, u16, String)>,
+    clears: usize,
+}
+
+impl HeadlessBackend {
+    pub fn new(width: u16, height: u16) -> Self {
+        HeadlessBackend {
+            width,
+            height,
+            cells: vec![vec![Cell::default(); width as usize]; height as usize],
+            cursor: (0, 0),
+            cursor_visible: true,
+            reversed: false,
+            printed: Vec::new(),
+            clears: 0,
+        }
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> &Cell {
+        &self.cells[y as usize][x as usize]
+    }
+
+    /// Row `y` of the screen without trailing blanks.
+    pub fn line(&self, y: u16) -> String {
+        let line: String = self.cells[y as usize]
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect();
+        line.trim_end().to_string()
+    }
+
+    /// All rows of the screen without trailing blanks.
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.height).map(|y| self.line(y)).collect()
+    }
+
+    pub fn cursor(&self) -> (u16, u16) {
+        self.cursor
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// Changes the size of the screen like a resized terminal window: what
+    /// still fits stays, the rest is cut off.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.cells
+            .resize(height as usize, vec![Cell::default(); width as usize]);
+        for row in &mut self.cells {
+            row.resize(width as usize, Cell::default());
+        }
+        self.width = width;
+        self.height = height;
+        self.cursor = (
+            self.cursor.0.min(width.saturating_sub(1)),
+            self.cursor.1.min(height.saturating_sub(1)),
+        );
+    }
+
+    /// Texts printed since the last call, each with the cell it started at.
+    pub fn take_printed(&mut self) -> Vec<(u16, u16, String)> {
+        std::mem::take(&mut self.printed)
+    }
+
+    /// How many times the whole screen was cleared.
+    pub fn clears(&self) -> usize {
+        self.clears
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn size(&self) -> std::io::Result<(u16, u16)> {
+        Ok((self.width, self.height))
+    }
+
+    fn clear(&mut self) -> std::io::Result<()> {
+        for row in &mut self.cells {
+            row.fill(Cell::default());
+        }
+        self.clears += 1;
+        Ok(())
+    }
+
+    fn move_to(&mut self, x: u16, y: u16) -> std::io::Result<()> {
+        self.cursor = (
+            x.min(self.width.saturating_sub(1)),
+            y.min(self.height.saturating_sub(1)),
+        );
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> std::io::Result<()> {
+        let (mut x, y) = self.cursor;
+        self.printed.push((x, y, text.to_string()));
+        for grapheme in text.graphemes(true) {
+            let width = unicode::grapheme_width(grapheme) as u16;
+            // text running past the right edge is clipped
+            if x + width > self.width {
+                break;
+            }
+            let row = &mut self.cells[y as usize];
+            row[x as usize] = Cell {
+                symbol: grapheme.to_string(),
+                reversed: self.reversed,
+            };
+            // the cells covered by a wide character stay empty
+            for covered in 1..width {
+                row[(x + covered) as usize] = Cell {
+                    symbol: String::new(),
+                    reversed: self.reversed,
+                };
+            }
+            x += width;
+        }
+        self.cursor = (x.min(self.width.saturating_sub(1)), y);
+        Ok(())
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) -> std::io::Result<()> {
+        self.cursor_visible = visible;
+        Ok(())
+    }
+
+    fn set_reversed(&mut self, reversed: bool) -> std::io::Result<()> {
+        self.reversed = reversed;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
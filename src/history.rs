@@ -0,0 +1,462 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+
+use crate::buffer::TextBuffer;
+use crate::{line_length, total_lines, MAX_FILE_SIZE};
+
+/// Most undo steps kept before the oldest ones are dropped.
+pub const MAX_UNDO_STEPS: usize = 1000;
+/// Most bytes of edited text kept before the oldest steps are dropped.
+pub const MAX_UNDO_BYTES: usize = 16 * 1024 * 1024;
+
+/// A single change of the buffer, enough to apply or revert it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    Insert { offset: usize, text: String },
+    Delete { offset: usize, text: String },
+}
+
+impl Edit {
+    fn apply(&self, buffer: &mut TextBuffer) {
+        match self {
+            Edit::Insert { offset, text } => buffer.insert(*offset, text),
+            Edit::Delete { offset, text } => buffer.remove(*offset..*offset + text.len()),
+        }
+    }
+
+    fn revert(&self, buffer: &mut TextBuffer) {
+        match self {
+            Edit::Insert { offset, text } => buffer.remove(*offset..*offset + text.len()),
+            Edit::Delete { offset, text } => buffer.insert(*offset, text),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Edit::Insert { text, .. } | Edit::Delete { text, .. } => text.len(),
+        }
+    }
+
+    /// Whether `apply` can be called on `buffer` without panicking.
+    fn can_apply(&self, buffer: &TextBuffer) -> bool {
+        match self {
+            Edit::Insert { offset, .. } => buffer.is_char_boundary(*offset),
+            Edit::Delete { offset, text } => contains_at(buffer, *offset, text),
+        }
+    }
+
+    /// Whether `revert` can be called on `buffer` without panicking.
+    fn can_revert(&self, buffer: &TextBuffer) -> bool {
+        match self {
+            Edit::Insert { offset, text } => contains_at(buffer, *offset, text),
+            Edit::Delete { offset, .. } => buffer.is_char_boundary(*offset),
+        }
+    }
+
+    fn write_to<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let (kind, offset, text) = match self {
+            Edit::Insert { offset, text } => ("insert", offset, text),
+            Edit::Delete { offset, text } => ("delete", offset, text),
+        };
+        writeln!(out, "{kind} {offset} {}", text.len())?;
+        out.write_all(text.as_bytes())?;
+        writeln!(out)
+    }
+
+    fn read_from<R: BufRead>(input: &mut R) -> std::io::Result<Edit> {
+        let fields = read_fields(input)?;
+        let [kind, offset, len] = fields.as_slice() else {
+            return Err(invalid("malformed edit"));
+        };
+        let offset = parse_number(offset)?;
+        // the length comes from the file, a corrupt one must not exhaust memory
+        let len = parse_number(len)?
+            .checked_add(1)
+            .filter(|&len| len as u64 <= MAX_FILE_SIZE)
+            .ok_or_else(|| invalid("edit too large"))?;
+        let mut text = vec![0; len];
+        input.read_exact(&mut text)?;
+        if text.pop() != Some(b'\n') {
+            return Err(invalid("edit text is not terminated"));
+        }
+        let text = String::from_utf8(text).map_err(|_| invalid("edit text is not UTF-8"))?;
+        match kind.as_str() {
+            "insert" => Ok(Edit::Insert { offset, text }),
+            "delete" => Ok(Edit::Delete { offset, text }),
+            _ => Err(invalid("unknown edit kind")),
+        }
+    }
+}
+
+/// How an edit may be merged with the undo step before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// Typed characters, consecutive ones are undone together.
+    Typing,
+    /// Backspaces and forward deletes, consecutive ones are undone together.
+    Deleting,
+    /// Always an undo step of its own.
+    Single,
+}
+
+/// Edits undone and redone together, with the cursor around them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoStep {
+    pub edits: Vec<Edit>,
+    pub cursor_before: (usize, usize),
+    pub cursor_after: (usize, usize),
+    grouping: Grouping,
+}
+
+impl UndoStep {
+    fn size(&self) -> usize {
+        self.edits.iter().map(Edit::len).sum()
+    }
+
+    fn write_to<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let grouping = match self.grouping {
+            Grouping::Typing => "typing",
+            Grouping::Deleting => "deleting",
+            Grouping::Single => "single",
+        };
+        let (before_x, before_y) = self.cursor_before;
+        let (after_x, after_y) = self.cursor_after;
+        writeln!(
+            out,
+            "step {grouping} {before_x} {before_y} {after_x} {after_y} {}",
+            self.edits.len()
+        )?;
+        for edit in &self.edits {
+            edit.write_to(out)?;
+        }
+        Ok(())
+    }
+
+    fn read_from<R: BufRead>(input: &mut R) -> std::io::Result<UndoStep> {
+        let fields = read_fields(input)?;
+        let [tag, grouping, numbers @ ..] = fields.as_slice() else {
+            return Err(invalid("malformed step"));
+        };
+        let grouping = match grouping.as_str() {
+            "typing" => Grouping::Typing,
+            "deleting" => Grouping::Deleting,
+            "single" => Grouping::Single,
+            _ => return Err(invalid("unknown grouping")),
+        };
+        let numbers = numbers
+            .iter()
+            .map(|field| parse_number(field))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let [before_x, before_y, after_x, after_y, count] = numbers[..] else {
+            return Err(invalid("malformed step"));
+        };
+        if tag != "step" {
+            return Err(invalid("expected a step"));
+        }
+        let edits = (0..count)
+            .map(|_| Edit::read_from(input))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Ok(UndoStep {
+            edits,
+            cursor_before: (before_x, before_y),
+            cursor_after: (after_x, after_y),
+            grouping,
+        })
+    }
+
+    /// Whether `edit` continues the typing or deleting of this step.
+    fn continues_with(&self, edit: &Edit, grouping: Grouping) -> bool {
+        match (self.edits.last(), edit) {
+            (Some(Edit::Insert { offset, text }), Edit::Insert { offset: next, .. })
+                if grouping == Grouping::Typing =>
+            {
+                *next == offset + text.len()
+            }
+            (Some(Edit::Delete { offset, .. }), Edit::Delete { offset: next, text })
+                if grouping == Grouping::Deleting =>
+            {
+                // backwards like Backspace, or in place like Delete
+                next + text.len() == *offset || next == offset
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Undo and redo stacks of the editor.
+///
+/// Memory is bounded by `MAX_UNDO_STEPS` and `MAX_UNDO_BYTES`, the oldest
+/// steps are forgotten first.
+///
+/// The history also remembers which step the saved file ends with, so
+/// undoing back to it makes the text unmodified again.
+#[derive(Debug)]
+pub struct History {
+    undo: VecDeque<UndoStep>,
+    redo: Vec<UndoStep>,
+    undo_bytes: usize,
+    sealed: bool,
+    /// Number of undo steps the saved text has, `None` when it cannot be
+    /// reached any more.
+    save_point: Option<usize>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            undo_bytes: 0,
+            sealed: false,
+            save_point: Some(0),
+        }
+    }
+}
+
+impl History {
+    /// An empty history of text that is saved as it is.
+    pub fn new() -> Self {
+        History::default()
+    }
+
+    /// Marks the current text as the saved one.
+    pub fn mark_saved(&mut self) {
+        self.save_point = Some(self.undo.len());
+        // an edit merged into the last step would change the saved text
+        self.sealed = true;
+    }
+
+    /// Forgets the save point, no undoing or redoing leads to saved text.
+    pub fn mark_unsaved(&mut self) {
+        self.save_point = None;
+    }
+
+    /// Whether the text is the saved one.
+    pub fn is_saved(&self) -> bool {
+        self.save_point == Some(self.undo.len())
+    }
+
+    /// Records `edit`, already applied to the buffer, and drops the redo stack.
+    pub fn record(
+        &mut self,
+        edit: Edit,
+        grouping: Grouping,
+        cursor_before: (usize, usize),
+        cursor_after: (usize, usize),
+    ) {
+        self.clear_redo();
+        self.undo_bytes += edit.len();
+        match self.undo.back_mut() {
+            Some(step)
+                if !self.sealed
+                    && step.grouping == grouping
+                    && step.continues_with(&edit, grouping) =>
+            {
+                step.edits.push(edit);
+                step.cursor_after = cursor_after;
+            }
+            _ => self.undo.push_back(UndoStep {
+                edits: vec![edit],
+                cursor_before,
+                cursor_after,
+                grouping,
+            }),
+        }
+        self.sealed = grouping == Grouping::Single;
+        self.trim();
+    }
+
+    /// Records several edits, already applied in order, as one undo step of
+    /// their own.
+    pub fn record_step(
+        &mut self,
+        edits: Vec<Edit>,
+        cursor_before: (usize, usize),
+        cursor_after: (usize, usize),
+    ) {
+        self.clear_redo();
+        let step = UndoStep {
+            edits,
+            cursor_before,
+            cursor_after,
+            grouping: Grouping::Single,
+        };
+        self.undo_bytes += step.size();
+        self.undo.push_back(step);
+        self.sealed = true;
+        self.trim();
+    }
+
+    /// Drops the undone steps, with the save point if it is among them.
+    fn clear_redo(&mut self) {
+        if self.save_point.is_some_and(|point| point > self.undo.len()) {
+            self.save_point = None;
+        }
+        self.redo.clear();
+    }
+
+    /// Closes the current undo step, the next edit starts a new one.
+    /// Called whenever the cursor moves on its own.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Reverts the last undo step and returns the cursor from before it.
+    pub fn undo(&mut self, buffer: &mut TextBuffer) -> Option<(usize, usize)> {
+        let step = self.undo.pop_back()?;
+        self.undo_bytes -= step.size();
+        for edit in step.edits.iter().rev() {
+            edit.revert(buffer);
+        }
+        let cursor = step.cursor_before;
+        self.redo.push(step);
+        self.sealed = true;
+        Some(cursor)
+    }
+
+    /// Applies the last undone step again and returns the cursor after it.
+    pub fn redo(&mut self, buffer: &mut TextBuffer) -> Option<(usize, usize)> {
+        let step = self.redo.pop()?;
+        for edit in &step.edits {
+            edit.apply(buffer);
+        }
+        let cursor = step.cursor_after;
+        self.undo_bytes += step.size();
+        self.undo.push_back(step);
+        self.sealed = true;
+        self.trim();
+        Some(cursor)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Number of steps that can be undone.
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Whether every step can be undone and redone on `buffer`, the text the
+    /// history ends with. A history read from a file is checked with it
+    /// before it is used.
+    pub fn applies_to(&self, buffer: &TextBuffer) -> bool {
+        let fits = |buffer: &TextBuffer, (x, y): (usize, usize)| {
+            y < total_lines(buffer) && x <= line_length(buffer, y)
+        };
+        let mut text = TextBuffer::from(buffer.to_string().as_str());
+        for step in self.undo.iter().rev() {
+            if !fits(&text, step.cursor_after) {
+                return false;
+            }
+            for edit in step.edits.iter().rev() {
+                if !edit.can_revert(&text) {
+                    return false;
+                }
+                edit.revert(&mut text);
+            }
+            if !fits(&text, step.cursor_before) {
+                return false;
+            }
+        }
+        let mut text = TextBuffer::from(buffer.to_string().as_str());
+        for step in self.redo.iter().rev() {
+            if !fits(&text, step.cursor_before) {
+                return false;
+            }
+            for edit in &step.edits {
+                if !edit.can_apply(&text) {
+                    return false;
+                }
+                edit.apply(&mut text);
+            }
+            if !fits(&text, step.cursor_after) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Writes both stacks in the format read back by `read_from`.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "undo {}", self.undo.len())?;
+        for step in &self.undo {
+            step.write_to(out)?;
+        }
+        writeln!(out, "redo {}", self.redo.len())?;
+        for step in &self.redo {
+            step.write_to(out)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a history written by `write_to` for the saved text. New edits
+    /// never merge into the steps read from it. Whether it fits the text is
+    /// checked by `applies_to`.
+    pub fn read_from<R: BufRead>(input: &mut R) -> std::io::Result<History> {
+        let mut history = History::new();
+        for name in ["undo", "redo"] {
+            let fields = read_fields(input)?;
+            let [tag, count] = fields.as_slice() else {
+                return Err(invalid("malformed stack header"));
+            };
+            if tag != name {
+                return Err(invalid("unexpected stack"));
+            }
+            for _ in 0..parse_number(count)? {
+                let step = UndoStep::read_from(input)?;
+                if name == "undo" {
+                    history.undo_bytes += step.size();
+                    history.undo.push_back(step);
+                } else {
+                    history.redo.push(step);
+                }
+            }
+        }
+        history.mark_saved();
+        history.trim();
+        Ok(history)
+    }
+
+    fn trim(&mut self) {
+        // the newest step is always kept, even when it is bigger than the limit
+        while self.undo.len() > MAX_UNDO_STEPS
+            || (self.undo_bytes > MAX_UNDO_BYTES && self.undo.len() > 1)
+        {
+            if let Some(step) = self.undo.pop_front() {
+                self.undo_bytes -= step.size();
+                self.save_point = self.save_point.and_then(|point| point.checked_sub(1));
+            }
+        }
+    }
+}
+
+/// Whether `text` is in `buffer` at byte `offset`, on character boundaries.
+fn contains_at(buffer: &TextBuffer, offset: usize, text: &str) -> bool {
+    offset.checked_add(text.len()).is_some_and(|end| {
+        buffer.is_char_boundary(offset)
+            && buffer.is_char_boundary(end)
+            && buffer.slice(offset..end) == text
+    })
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+fn parse_number(field: &str) -> std::io::Result<usize> {
+    field.parse().map_err(|_| invalid("malformed number"))
+}
+
+/// Reads one header line and splits it on spaces.
+fn read_fields<R: BufRead>(input: &mut R) -> std::io::Result<Vec<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(line.split_whitespace().map(str::to_string).collect())
+}
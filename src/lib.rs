@@ -1,81 +1,216 @@
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
 
-pub fn initialize_text_buffer(
+use error::WithPath;
+
+pub mod backend;
+pub mod buffer;
+pub mod buffers;
+pub mod cli;
+pub mod clipboard;
+pub mod config;
+pub mod editor;
+pub mod encoding;
+pub mod error;
+pub mod event_loop;
+pub mod gutter;
+pub mod headless;
+pub mod history;
+pub mod line_ending;
+pub mod motion;
+pub mod render;
+pub mod save;
+pub mod status;
+pub mod swap;
+pub mod terminal;
+pub mod undo_file;
+pub mod unicode;
+pub mod viewport;
+pub mod wrap;
+
+pub use backend::{Backend, CrosstermBackend, CrosstermEvents, EventSource};
+pub use buffer::TextBuffer;
+pub use buffers::Buffers;
+pub use clipboard::{Clipboard, InternalClipboard, SharedClipboard, SystemClipboard};
+pub use config::Config;
+pub use editor::{Action, Editor, Prompt};
+pub use encoding::Encoding;
+pub use error::{EditorError, MAX_FILE_SIZE};
+pub use event_loop::run;
+pub use gutter::LineNumbers;
+pub use headless::{HeadlessBackend, ScriptedEvents};
+pub use history::History;
+pub use line_ending::LineEnding;
+pub use motion::WordChars;
+pub use swap::SwapFile;
+pub use terminal::TerminalGuard;
+pub use viewport::Viewport;
+pub use wrap::Wrap;
+
+/// A file loaded for editing.
+pub struct LoadedText {
+    pub buffer: TextBuffer,
+    pub cursor: (usize, usize),
+    /// Line endings of the file, the buffer itself always uses `\n`.
+    pub line_ending: LineEnding,
+    /// Unsaved text left in a swap file by an editor that did not quit.
+    pub recovery: Option<SwapFile>,
+    /// Something about the file to tell the user once it is shown.
+    pub warning: Option<String>,
+}
+
+impl LoadedText {
+    /// `text` as a new buffer with nothing to recover. The
+    /// cursor is at the end.
+    pub fn from_text(text: &str) -> LoadedText {
+        let line_ending = LineEnding::detect(text);
+        let buffer = TextBuffer::from(line_ending::normalize(text).as_str());
+
+        // Calculate initial cursor position
+        let cursor = {
+            let last_line = buffer.line_count() - 1;
+            let last_line_length = unicode::grapheme_count(&buffer.line(last_line));
+            (last_line_length, last_line)
+        };
+
+        // saving rewrites the lines in the minority style
+        let warning = LineEnding::is_mixed(text)
+            .then(|| format!("Mixed line endings, saving as {}", line_ending.name()));
+
+        LoadedText {
+            buffer,
+            cursor,
+            line_ending,
+            recovery: None,
+            warning,
+        }
+    }
+}
+
+pub fn initialize_text_buffer(file_name: &str) -> error::Result<LoadedText> {
+    initialize_text_buffer_with(file_name, Encoding::Utf8)
+}
+
+/// Loads `file_name` stored in `encoding`, an empty text if it does not exist.
+pub fn initialize_text_buffer_with(
     file_name: &str,
-) -> std::io::Result<(Arc<Mutex<String>>, (u16, u16))> {
+    encoding: Encoding,
+) -> error::Result<LoadedText> {
     // Load existing file content if it exists
-    let initial_content = if Path::new(file_name).exists() {
-        let mut file = File::open(file_name)?;
-        let mut content = String::new();
-        file.read_to_string(&mut content)?;
-        content
+    let path = Path::new(file_name);
+    let exists = path.exists();
+    let initial_content = if exists {
+        let mut file = File::open(path).with_path(path)?;
+        let size = file.metadata().with_path(path)?.len();
+        if size > MAX_FILE_SIZE {
+            return Err(EditorError::TooLarge {
+                path: path.to_path_buf(),
+                size,
+            });
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).with_path(path)?;
+        encoding
+            .decode(content)
+            .ok_or_else(|| EditorError::InvalidEncoding(path.to_path_buf()))?
     } else {
         String::new()
     };
 
-    let inserted_text = Arc::new(Mutex::new(initial_content));
+    let mut loaded = LoadedText::from_text(&initial_content);
+    let initial_content = line_ending::normalize(&initial_content);
 
-    // Calculate initial cursor position
-    let cursor_position = {
-        let text = inserted_text.lock().unwrap();
-        let last_line = text.lines().count().max(1) - 1;
-        let last_line_length = text.lines().last().unwrap_or("").len();
-        (last_line_length as u16, last_line as u16)
+    // A swap file nobody is writing any more holds changes lost in a crash,
+    // one with nothing new in it is just cleaned up. A swap file somebody
+    // seems to be still writing is offered too, the editor warns about it:
+    // the process id may just as well have been reused after a crash. It is
+    // never removed, and the warning stands even with nothing to recover.
+    loaded.recovery = match swap::read(path) {
+        Ok(Some(swap_file)) if swap_file.text == initial_content => {
+            if swap_file.is_stale() {
+                swap::remove(path)?;
+            } else {
+                let locked = EditorError::Locked {
+                    path: path.to_path_buf(),
+                    pid: swap_file.pid,
+                };
+                loaded.warning = Some(format!("Warning: {locked}"));
+            }
+            None
+        }
+        Ok(swap_file) => swap_file,
+        Err(_) => None,
     };
 
-    Ok((inserted_text, cursor_position))
+    Ok(loaded)
+}
+
+/// Line lookups the helpers below are built on.
+///
+/// Plain strings answer them by scanning the text, `TextBuffer` answers them
+/// from the newline counts kept in its rope, without walking the whole text.
+pub trait TextLines {
+    /// Number of lines, empty text has one empty line.
+    fn line_count(&self) -> usize;
+    /// Byte offset where line `y` starts, the end of the text past the last line.
+    fn line_start(&self, y: usize) -> usize;
+    /// Text of line `y` without its line break, empty past the last line.
+    fn line_text(&self, y: usize) -> Cow<'_, str>;
 }
 
+impl TextLines for str {
+    fn line_count(&self) -> usize {
+        self.bytes().filter(|&b| b == b'\n').count() + 1
+    }
 
-/// Calculates the index into `Vec<char>` based on the cursor position (x, y).
-pub fn  position_to_index(text: &str, cursor: (u16, u16)) -> usize {
-    let (x, y) = cursor;
-    let mut line = 0;
-    let mut index = 0;
-    for (i, ch) in text.chars().enumerate() {
-        if line == y {
-            if i >= text.len() {
-                return index;
-            }
-            if index + x as usize <= text.len() {
-                return index + x as usize;
-            } else {
-                return text.len();
-            }
-        }
-        index += ch.len_utf8(); // Jump over the appropriate length of the sign
-        if ch == '\n' {
-            line += 1;
-        }
-        if line > y {
-            break;
+    fn line_start(&self, y: usize) -> usize {
+        if y == 0 {
+            return 0;
         }
+        self.match_indices('\n')
+            .nth(y - 1)
+            .map_or(self.len(), |(index, _)| index + 1)
+    }
+
+    fn line_text(&self, y: usize) -> Cow<'_, str> {
+        Cow::Borrowed(self.split('\n').nth(y).unwrap_or(""))
     }
-    index // By default it returns the end of text
 }
 
-/// Calculates the length of a line from `y`.
-pub fn line_length(text: &str, y: usize) -> u16 {
-    let mut line = 0;
-    let mut length = 0;
+impl TextLines for TextBuffer {
+    fn line_count(&self) -> usize {
+        TextBuffer::line_count(self)
+    }
 
-    for ch in text.chars() {
-        if line == y {
-            if ch == '\n' {
-                break;
-            }
-            length += 1;
-        } else if ch == '\n' {
-            line += 1;
+    fn line_start(&self, y: usize) -> usize {
+        TextBuffer::line_start(self, y)
+    }
+
+    fn line_text(&self, y: usize) -> Cow<'_, str> {
+        if y < self.line_count() {
+            Cow::Owned(self.line(y))
+        } else {
+            Cow::Borrowed("")
         }
     }
-    length
+}
+
+/// Calculates the byte index into the text based on the cursor position (x, y),
+/// where `x` counts grapheme clusters. Positions past the end of a line or past
+/// the last line are clamped.
+pub fn position_to_index<T: TextLines + ?Sized>(text: &T, cursor: (usize, usize)) -> usize {
+    let (x, y) = cursor;
+    text.line_start(y) + unicode::byte_offset(&text.line_text(y), x)
+}
+
+/// Calculates the length of a line from `y`, in grapheme clusters.
+pub fn line_length<T: TextLines + ?Sized>(text: &T, y: usize) -> usize {
+    unicode::grapheme_count(&text.line_text(y))
 }
 
 /// Calculates the entire line area of text.
-pub fn total_lines(text: &str) -> usize {
-    text.chars().filter(|&ch| ch == '\n').count() + 1
+pub fn total_lines<T: TextLines + ?Sized>(text: &T) -> usize {
+    text.line_count()
 }
@@ -0,0 +1,53 @@
+//! Line endings of the files being edited.
+//!
+//! The buffer always separates lines with `\n`. A file written with `\r\n`
+//! is converted when it is loaded and converted back when it is saved, so
+//! editing never mixes the two styles. A file which already mixes them is
+//! saved in the style most of its lines use, with a warning when it is opened.
+
+/// How lines are separated in the file on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /// The style most lines of `text` end with, `Lf` when there are none.
+    pub fn detect(text: &str) -> LineEnding {
+        let lines = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+        if crlf > 0 && crlf * 2 >= lines {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Whether some lines of `text` end with `\r\n` and others with `\n`.
+    pub fn is_mixed(text: &str) -> bool {
+        let crlf = text.matches("\r\n").count();
+        crlf > 0 && crlf < text.matches('\n').count()
+    }
+
+    /// Name shown in the status bar.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// `text` with every `\r\n` turned into `\n`, as the buffer keeps it.
+pub fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
@@ -1,208 +1,64 @@
+use rust_terminal_notepad::cli::{self, Command, Options};
 use rust_terminal_notepad::*;
 
-use std::{
-    fs::File,
-    io::Write,
-    sync::mpsc,
-    thread,
-    time::Duration,
-};
+use std::panic::{self, AssertUnwindSafe};
 
-use crossterm::event::KeyEventKind;
-use crossterm::{
-    cursor,
-    event::{self, Event, KeyCode, KeyModifiers},
-    execute,
-    style::Print,
-    terminal::{self},
-};
-
-fn main() -> std::io::Result<()> {
-    let file_name;
-    if std::env::args().count() < 2 {
-        file_name = "untitled.txt".to_string();
-    } else {
-        file_name = std::env::args().collect::<Vec<_>>()[1].clone().to_string();
-    }
-
-    // terminal init
-    let mut stdout = std::io::stdout();
-    terminal::enable_raw_mode()?;
-    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
-
-    // Initialize text buffer and cursor position
-    let (inserted_text, mut cursor_position) = initialize_text_buffer(&file_name)?;
-
-    let mut show_cursor = true;
-
-    // Setup communication channel for blinking cursor
-    let (tx, rx) = mpsc::channel();
-
-    // Start a thread for blinking the cursor
-    let _blink_thread = thread::spawn(move || loop {
-        tx.send(()).unwrap();
-        thread::sleep(Duration::from_millis(500));
-    });
-
-    loop {
-        // clear screen and show buffer
-        {
-            // put it in a separate scope to free up the mutex on the inserted_text variable
-            let text = inserted_text.lock().unwrap();
-            execute!(
-                stdout,
-                terminal::Clear(terminal::ClearType::All),
-                cursor::MoveTo(0, 0),
-                Print(&*text),
-                cursor::MoveTo(cursor_position.0, cursor_position.1),
-            )?;
-            if show_cursor {
-                execute!(stdout, cursor::Show)?;
-            } else {
-                execute!(stdout, cursor::Hide)?;
-            }
+fn main() {
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Help) => {
+            print!("{}", cli::HELP);
+            return;
         }
-
-        if let Ok(_) = rx.try_recv() {
-            show_cursor = !show_cursor;
+        Ok(Command::Version) => {
+            println!("{}", cli::version());
+            return;
         }
+        Ok(Command::Edit(options)) => options,
+        Err(error) => {
+            eprintln!("rust_terminal_notepad: {error}");
+            eprintln!("Try 'rust_terminal_notepad --help' for more information.");
+            std::process::exit(2);
+        }
+    };
 
-        if let Event::Key(key_event) = event::read()? {
-            if key_event.kind != KeyEventKind::Release {
-                // skip releasing the button
-                match key_event {
-                    event::KeyEvent {
-                        code: KeyCode::Char(c),
-                        modifiers: KeyModifiers::NONE,
-                        ..
-                    } => {
-                        let mut text = inserted_text.lock().unwrap();
-
-                        let index = position_to_index(&text, cursor_position);
-
-                        text.insert(index, c);
-
-                        cursor_position.0 += 1; // move cursor to right
-
-                    }
-
-                    event::KeyEvent {
-                        code: KeyCode::Char(c),
-                        modifiers: KeyModifiers::SHIFT,
-                        ..
-                    } => {
-                        let mut text = inserted_text.lock().unwrap();
-                        text.push(c);
-                        cursor_position.0 += 1;
-                    }
-
-                    event::KeyEvent {
-                        code: KeyCode::Backspace,
-                        ..
-                    } => {
-                        let mut text = inserted_text.lock().unwrap();
-                        if !text.is_empty() {
-                            if cursor_position.0 > 0 || cursor_position.1 >0 {
-                                let index = position_to_index(&text,  cursor_position);
-                                if index > 0 {
-                                    text.remove(index - 1);
-                                }
-                                // Move cursor left
-                                if cursor_position.0 > 0 {
-                                    cursor_position.0 -= 1;
-                                } else if cursor_position.1 > 0 {
-                                    cursor_position.1 -= 1;
-                                    cursor_position.0 = line_length(&text, cursor_position.1 as usize);
-                                }
-
-                            }
-                        }
-                    }
-
-                    event::KeyEvent {
-                        code: KeyCode::Enter,
-                        ..
-                    } => {
-                        let mut text = inserted_text.lock().unwrap();
-                        let index = position_to_index(&text, cursor_position);
-                        text.insert(index, '\n');
-                        cursor_position.0 = 0;
-                        cursor_position.1 += 1;
-                    }
-
-                    event::KeyEvent {
-                        code: KeyCode::Esc, ..
-                    } => {
-                        break;
-                    }
-                    event::KeyEvent {
-                        code: KeyCode::Char('c'),
-                        modifiers: KeyModifiers::CONTROL,
-                        ..
-                    } => {
-                        let text = inserted_text.lock().unwrap();
-                        let mut file = File::create(&file_name)?;
-                        file.write_all(text.as_bytes())?;
-                        break;
-                    }
-
-                    event::KeyEvent {
-                        code: KeyCode::Left, ..
-                    } => {
-                        if cursor_position.0 > 0 {
-                            cursor_position.0 -= 1;
-                        } else if  cursor_position.1 > 0 {
-                            let text = inserted_text.lock().unwrap();
-                            let lines: Vec<&str> = text.split('\n').collect();
-                            if let Some(prev_line) = lines.get(cursor_position.1 as usize - 1) {
-                                cursor_position.1 -= 1;
-                                cursor_position.0 = prev_line.len() as u16; // Set cursor to the end of the previous line
-                            }
-                        }
-
-                    }
-
-                    event::KeyEvent {
-                        code: KeyCode::Right, ..
-                    } => {
-                        let text = inserted_text.lock().unwrap();
-                        if cursor_position.0 < text.len() as u16 {
-                            cursor_position.0 += 1;
-                        } else if cursor_position.1 < total_lines(&text) as u16 - 1 {
-                            cursor_position.1 += 1;
-                            cursor_position.0 = 0;
-                        }
+    if let Err(error) = edit(&options) {
+        eprintln!("rust_terminal_notepad: {error}");
+        std::process::exit(1);
+    }
+}
 
+fn edit(options: &Options) -> Result<(), EditorError> {
+    // Initialize text buffers and cursor positions
+    let mut buffers = cli::open(options, &mut std::io::stdin())?;
+    let (width, height) = crossterm::terminal::size()?;
+    buffers.resize(width, height);
+    buffers.set_clipboard(clipboard::shared(SystemClipboard::detect()));
+    buffers.set_undo_dir(undo_file::undo_dir());
+
+    // terminal init, restored when the guard drops or by the panic hook
+    terminal::install_panic_hook();
+    let guard = TerminalGuard::enter()?;
+
+    let mut backend = CrosstermBackend::new(std::io::BufWriter::new(std::io::stdout()));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        run(&mut buffers, &mut CrosstermEvents, &mut backend)
+    }));
+    drop(guard);
+
+    match result {
+        Ok(result) => result,
+        Err(_) => {
+            // the panic message is printed already, save what can be saved
+            for editor in buffers.editors_mut() {
+                match editor.emergency_dump() {
+                    Ok(Some(path)) => {
+                        eprintln!("Unsaved changes were saved to {}", path.display())
                     }
-                    event::KeyEvent {
-                        code: KeyCode::Up,
-                        modifiers: KeyModifiers::NONE,
-                        ..
-                    } => {
-                        if cursor_position.1 > 0 {
-                            cursor_position.1 -= 1;
-                            let text = inserted_text.lock().unwrap();
-                            cursor_position.0 = cursor_position.0.min(line_length(&text, cursor_position.1 as usize));
-                        }
-                    }
-                    event::KeyEvent {
-                        code: KeyCode::Down,
-                        modifiers: KeyModifiers::NONE,
-                        ..
-                    } => {
-                        let text = inserted_text.lock().unwrap();
-                        if cursor_position.1 < total_lines(&text) as u16 - 1 {
-                            cursor_position.1 += 1;
-                            cursor_position.0 = cursor_position.0.min(line_length(&text, cursor_position.1 as usize));
-                        }
-                    }
-
-                    _ => {}
+                    Ok(None) => {}
+                    Err(error) => eprintln!("Unsaved changes could not be saved: {error}"),
                 }
             }
+            std::process::exit(101);
         }
     }
-    execute!(stdout, terminal::LeaveAlternateScreen, cursor::Show)?;
-    terminal::disable_raw_mode()?;
-    Ok(())
 }
@@ -0,0 +1,250 @@
+//! Moving by words, sentences and paragraphs.
+//!
+//! Positions are (column, line) like the cursor's, columns count grapheme
+//! clusters. Everything works on `TextLines`, so the same motions apply to
+//! the text buffer and to plain strings.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{line_length, total_lines, TextLines};
+
+/// Characters besides letters and digits that are part of a word unless
+/// configured otherwise.
+pub const DEFAULT_WORD_CHARS: &str = "_";
+
+/// Characters ending a sentence.
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', '…', '。', '！', '？'];
+/// Characters that may follow the end of a sentence before the space after it.
+const CLOSING: &[char] = &[')', ']', '}', '"', '\'', '’', '”', '»'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+/// What a word is made of: letters and digits of any script, and the
+/// characters chosen in addition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordChars {
+    extra: String,
+}
+
+impl WordChars {
+    pub fn new(extra: &str) -> Self {
+        WordChars {
+            extra: extra.to_string(),
+        }
+    }
+
+    /// The characters that belong to words besides letters and digits.
+    pub fn extra(&self) -> &str {
+        &self.extra
+    }
+
+    // a grapheme is classified by its base character, so combining marks
+    // stay with the letter they belong to
+    fn class(&self, grapheme: &str) -> Class {
+        let Some(c) = grapheme.chars().next() else {
+            return Class::Blank;
+        };
+        if c.is_whitespace() {
+            Class::Blank
+        } else if c.is_alphanumeric() || self.extra.contains(c) {
+            Class::Word
+        } else {
+            Class::Punctuation
+        }
+    }
+
+    fn classes(&self, line: &str) -> Vec<Class> {
+        line.graphemes(true).map(|g| self.class(g)).collect()
+    }
+}
+
+impl Default for WordChars {
+    fn default() -> Self {
+        WordChars::new(DEFAULT_WORD_CHARS)
+    }
+}
+
+/// Start of the next word after `(x, y)`, the end of the line when no other
+/// word follows on it. From the end of a line, the start of the next line.
+pub fn word_right<T: TextLines + ?Sized>(
+    text: &T,
+    (x, y): (usize, usize),
+    word_chars: &WordChars,
+) -> (usize, usize) {
+    let classes = word_chars.classes(&text.line_text(y));
+    if x >= classes.len() {
+        return if y + 1 < total_lines(text) {
+            (0, y + 1)
+        } else {
+            (classes.len(), y)
+        };
+    }
+    let mut x = x;
+    let class = classes[x];
+    if class != Class::Blank {
+        while x < classes.len() && classes[x] == class {
+            x += 1;
+        }
+    }
+    while x < classes.len() && classes[x] == Class::Blank {
+        x += 1;
+    }
+    (x, y)
+}
+
+/// Start of the word before `(x, y)`. From the start of a line, the end of
+/// the line above.
+pub fn word_left<T: TextLines + ?Sized>(
+    text: &T,
+    (x, y): (usize, usize),
+    word_chars: &WordChars,
+) -> (usize, usize) {
+    if x == 0 {
+        return if y > 0 {
+            (line_length(text, y - 1), y - 1)
+        } else {
+            (0, 0)
+        };
+    }
+    let classes = word_chars.classes(&text.line_text(y));
+    let mut x = x.min(classes.len());
+    while x > 0 && classes[x - 1] == Class::Blank {
+        x -= 1;
+    }
+    if x > 0 {
+        let class = classes[x - 1];
+        while x > 0 && classes[x - 1] == class {
+            x -= 1;
+        }
+    }
+    (x, y)
+}
+
+/// Whether line `y` holds nothing but whitespace.
+pub fn is_blank_line<T: TextLines + ?Sized>(text: &T, y: usize) -> bool {
+    text.line_text(y).trim().is_empty()
+}
+
+/// The blank line after the paragraph at or below line `y`, the end of the
+/// text after the last paragraph.
+pub fn paragraph_down<T: TextLines + ?Sized>(text: &T, y: usize) -> (usize, usize) {
+    let last = total_lines(text) - 1;
+    let mut y = y;
+    while y < last && is_blank_line(text, y) {
+        y += 1;
+    }
+    while y < last && !is_blank_line(text, y) {
+        y += 1;
+    }
+    if is_blank_line(text, y) {
+        (0, y)
+    } else {
+        (line_length(text, y), y)
+    }
+}
+
+/// The blank line before the paragraph at or above line `y`, the start of
+/// the text before the first paragraph.
+pub fn paragraph_up<T: TextLines + ?Sized>(text: &T, y: usize) -> (usize, usize) {
+    let mut y = y;
+    while y > 0 && is_blank_line(text, y) {
+        y -= 1;
+    }
+    while y > 0 && !is_blank_line(text, y) {
+        y -= 1;
+    }
+    (0, y)
+}
+
+/// Start of the next sentence after `(x, y)`, the end of the text when
+/// there is none.
+///
+/// A sentence starts after `.`, `!` or `?` (optionally followed by closing
+/// quotes or brackets) and whitespace, and at the start of a paragraph.
+pub fn sentence_right<T: TextLines + ?Sized>(text: &T, (x, y): (usize, usize)) -> (usize, usize) {
+    let mut next = None;
+    for_each_sentence_start(text, paragraph_up(text, y).1, |(start_x, start_y)| {
+        if (start_y, start_x) > (y, x) {
+            next = Some((start_x, start_y));
+            return false;
+        }
+        true
+    });
+    next.unwrap_or_else(|| {
+        let last = total_lines(text) - 1;
+        (line_length(text, last), last)
+    })
+}
+
+/// Start of the sentence before `(x, y)`, the start of the text when there
+/// is none.
+pub fn sentence_left<T: TextLines + ?Sized>(text: &T, (x, y): (usize, usize)) -> (usize, usize) {
+    let mut from = paragraph_up(text, y).1;
+    loop {
+        let mut previous = None;
+        for_each_sentence_start(text, from, |(start_x, start_y)| {
+            // positions are ordered by line first
+            let before = (start_y, start_x) < (y, x);
+            if before {
+                previous = Some((start_x, start_y));
+            }
+            before
+        });
+        match previous {
+            Some(start) => return start,
+            None if from == 0 => return (0, 0),
+            // the cursor is at the first sentence of its paragraph
+            None => from = paragraph_up(text, from).1,
+        }
+    }
+}
+
+/// Calls `visit` with every sentence start from line `from` on, in order,
+/// until it returns `false`.
+fn for_each_sentence_start<T, F>(text: &T, from: usize, mut visit: F)
+where
+    T: TextLines + ?Sized,
+    F: FnMut((usize, usize)) -> bool,
+{
+    #[derive(PartialEq)]
+    enum State {
+        Inside,
+        /// Right after the end of a sentence.
+        Ended,
+        /// Whitespace after the end of a sentence, the next character
+        /// starts a new one.
+        Gap,
+    }
+
+    // the first line scanned is the start of a paragraph
+    let mut state = State::Gap;
+    for y in from..total_lines(text) {
+        let line = text.line_text(y);
+        for (x, grapheme) in line.graphemes(true).enumerate() {
+            let Some(c) = grapheme.chars().next() else {
+                continue;
+            };
+            if c.is_whitespace() {
+                if state == State::Ended {
+                    state = State::Gap;
+                }
+                continue;
+            }
+            if state == State::Gap && !visit((x, y)) {
+                return;
+            }
+            let ends =
+                SENTENCE_ENDS.contains(&c) || (CLOSING.contains(&c) && state == State::Ended);
+            state = if ends { State::Ended } else { State::Inside };
+        }
+        // a line break is whitespace, a blank line ends the paragraph
+        if state == State::Ended || line.trim().is_empty() {
+            state = State::Gap;
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Drawing the editor on a `Backend`.
+//!
+//! Every draw composes a `Frame`, the full content of the screen, and
+//! compares it with the frame drawn before. Only the cells that changed are
+//! printed, so typing a character rewrites a few cells instead of the whole
+//! screen, which keeps slow connections from flickering.
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::backend::Backend;
+use crate::editor::Editor;
+use crate::status;
+use crate::unicode;
+use crate::wrap::{self, Wrap};
+
+/// What the screen shows: the symbol of every cell and the cursor.
+///
+/// The cell after a wide character has an empty symbol, blank cells hold a
+/// space. Selected cells are drawn in reverse video.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    width: u16,
+    height: u16,
+    cells: Vec<Vec<String>>,
+    reversed: Vec<Vec<bool>>,
+    cursor: (u16, u16),
+    cursor_visible: bool,
+}
+
+impl Frame {
+    pub fn new(width: u16, height: u16) -> Self {
+        Frame {
+            width,
+            height,
+            cells: vec![vec![" ".to_string(); width as usize]; height as usize],
+            reversed: vec![vec![false; width as usize]; height as usize],
+            cursor: (0, 0),
+            cursor_visible: true,
+        }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Row `y` of the frame without trailing blanks.
+    pub fn line(&self, y: u16) -> String {
+        let line: String = self.cells[y as usize].concat();
+        line.trim_end().to_string()
+    }
+
+    /// Whether the cell at `x` in row `y` is drawn in reverse video.
+    pub fn is_reversed(&self, x: u16, y: u16) -> bool {
+        self.reversed[y as usize][x as usize]
+    }
+
+    /// Draws the cells `columns` of row `y` in reverse video, clipped at the
+    /// right edge.
+    pub fn reverse(&mut self, y: u16, columns: Range<usize>) {
+        if let Some(row) = self.reversed.get_mut(y as usize) {
+            let end = columns.end.min(row.len());
+            for reversed in &mut row[columns.start.min(end)..end] {
+                *reversed = true;
+            }
+        }
+    }
+
+    /// Puts `text` in row `y` from column `x` on, clipped at the right edge.
+    pub fn print(&mut self, x: u16, y: u16, text: &str) {
+        let Some(row) = self.cells.get_mut(y as usize) else {
+            return;
+        };
+        let mut x = x as usize;
+        for grapheme in text.graphemes(true) {
+            let width = unicode::grapheme_width(grapheme);
+            if x + width > row.len() {
+                break;
+            }
+            row[x] = grapheme.to_string();
+            for covered in &mut row[x + 1..x + width] {
+                covered.clear();
+            }
+            x += width;
+        }
+    }
+}
+
+/// Puts together the frame showing `editor` on a screen of the given size.
+///
+/// The text in the editor's viewport fills the screen except for the bottom
+/// row, which is left to the status bar.
+pub fn compose(editor: &Editor, width: u16, height: u16, show_cursor: bool) -> Frame {
+    let mut frame = Frame::new(width, height);
+    let rows = (height as usize).saturating_sub(1);
+    let viewport = editor.viewport();
+    let gutter_width = editor.gutter_width() as u16;
+
+    let buffer = editor.buffer();
+    let diff = editor.recovery_diff();
+    match &diff {
+        Some(diff) => {
+            for (row, line) in diff.iter().take(rows).enumerate() {
+                frame.print(0, row as u16, &unicode::display_line(&line.to_display()));
+            }
+        }
+        None if editor.wrap() == Wrap::Off => {
+            let lines = viewport.top..buffer.line_count().min(viewport.top + rows);
+            for (row, y) in lines.enumerate() {
+                let text = buffer.line(y);
+                let line = unicode::display_slice(&text, viewport.left, viewport.width);
+                frame.print(0, row as u16, &gutter(editor, y, 0));
+                frame.print(gutter_width, row as u16, &line);
+                if let Some(cells) = selected_cells(editor, y, &text, &(0..usize::MAX), 0) {
+                    // only the part scrolled into view
+                    let right = viewport.left + viewport.width;
+                    let (start, end) = (cells.start.max(viewport.left), cells.end.min(right));
+                    if start < end {
+                        let offset = gutter_width as usize;
+                        let visible = start - viewport.left + offset..end - viewport.left + offset;
+                        frame.reverse(row as u16, visible);
+                    }
+                }
+            }
+        }
+        None => {
+            // the top line may start with a row scrolled out of view
+            let mut screen_rows = (viewport.top..buffer.line_count())
+                .flat_map(|y| {
+                    let line = buffer.line(y);
+                    editor
+                        .rows(y)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(row, range)| {
+                            let text = wrap::display_row(wrap::row_text(&line, &range), row);
+                            let selected = selected_cells(editor, y, &line, &range, row);
+                            (y, row, text, selected)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .skip(viewport.top_row);
+            for screen_row in 0..rows {
+                let Some((y, row, text, selected)) = screen_rows.next() else {
+                    break;
+                };
+                frame.print(0, screen_row as u16, &gutter(editor, y, row));
+                frame.print(gutter_width, screen_row as u16, &text);
+                if let Some(cells) = selected {
+                    let offset = gutter_width as usize;
+                    frame.reverse(screen_row as u16, cells.start + offset..cells.end + offset);
+                }
+            }
+        }
+    }
+
+    // the bottom row shows the status bar, or the question waiting for an answer
+    let bottom = match editor.prompt() {
+        Some(prompt) => prompt.question().to_string(),
+        None => status::status_line(editor, width as usize),
+    };
+    frame.print(0, height.saturating_sub(1), &bottom);
+
+    let (x, y) = editor.screen_cursor();
+    frame.cursor = (x as u16, y as u16);
+    // there is nothing to edit while the diff is shown
+    frame.cursor_visible = show_cursor && diff.is_none();
+    frame
+}
+
+/// Cells of the selection in row `row` of line `y`, which holds the
+/// graphemes `columns` of `line`. A selected line break takes one cell after
+/// the end of the line.
+fn selected_cells(
+    editor: &Editor,
+    y: usize,
+    line: &str,
+    columns: &Range<usize>,
+    row: usize,
+) -> Option<Range<usize>> {
+    let ((start_x, start_y), (end_x, end_y)) = editor.selection()?;
+    if !(start_y..=end_y).contains(&y) {
+        return None;
+    }
+    let length = unicode::grapheme_count(line);
+    let columns = columns.start..columns.end.min(length);
+    let line_break = y < end_y && columns.end == length;
+    let start = if y == start_y { start_x } else { 0 }.clamp(columns.start, columns.end);
+    let end = if y == end_y { end_x } else { length }.clamp(start, columns.end);
+    if start == end && !line_break {
+        return None;
+    }
+    let text = wrap::row_text(line, &columns);
+    let cell = |column: usize| {
+        wrap::marker_width(row) + unicode::width_before(text, column - columns.start)
+    };
+    Some(cell(start)..cell(end) + usize::from(line_break))
+}
+
+/// Gutter next to row `row` of line `y`, rows continuing a wrapped line
+/// have no number.
+fn gutter(editor: &Editor, y: usize, row: usize) -> String {
+    let width = editor.gutter_width();
+    if row > 0 {
+        return " ".repeat(width);
+    }
+    editor.line_numbers().label(y, editor.cursor().1, width)
+}
+
+/// Draws frames on a backend, printing only what changed since the last one.
+#[derive(Debug, Default)]
+pub struct Renderer {
+    previous: Option<Frame>,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Renderer::default()
+    }
+
+    /// Forgets the last frame, the next draw repaints the whole screen.
+    pub fn invalidate(&mut self) {
+        self.previous = None;
+    }
+
+    /// Draws `editor` and places the cursor.
+    pub fn draw<B: Backend>(
+        &mut self,
+        editor: &Editor,
+        backend: &mut B,
+        show_cursor: bool,
+    ) -> std::io::Result<()> {
+        let (width, height) = backend.size()?;
+        let frame = compose(editor, width, height, show_cursor);
+
+        backend.begin_update()?;
+        match self.previous.take() {
+            Some(previous) if previous.size() == frame.size() => {
+                for y in 0..height {
+                    draw_changes(backend, &previous, &frame, y)?;
+                }
+            }
+            // the first frame, or the screen changed size
+            _ => {
+                backend.clear()?;
+                for y in 0..height {
+                    // trailing blanks are cleared already, unless they are selected
+                    let row = y as usize;
+                    let end = (0..width as usize)
+                        .rfind(|&x| frame.cells[row][x] != " " || frame.reversed[row][x])
+                        .map_or(0, |x| x + 1);
+                    if end > 0 {
+                        print_cells(backend, &frame, y, 0..end)?;
+                    }
+                }
+            }
+        }
+        backend.move_to(frame.cursor.0, frame.cursor.1)?;
+        backend.set_cursor_visible(frame.cursor_visible)?;
+        backend.end_update()?;
+        backend.flush()?;
+
+        self.previous = Some(frame);
+        Ok(())
+    }
+}
+
+/// Prints the part of row `y` between the first and the last cell that
+/// differ between the two frames.
+fn draw_changes<B: Backend>(
+    backend: &mut B,
+    previous: &Frame,
+    frame: &Frame,
+    y: u16,
+) -> std::io::Result<()> {
+    let old = &previous.cells[y as usize];
+    let new = &frame.cells[y as usize];
+    let changed = |x: usize| {
+        old[x] != new[x] || previous.reversed[y as usize][x] != frame.reversed[y as usize][x]
+    };
+    let Some(mut start) = (0..new.len()).find(|&x| changed(x)) else {
+        return Ok(());
+    };
+    let mut end = (0..new.len()).rfind(|&x| changed(x)).unwrap_or(start) + 1;
+
+    // a wide character is printed whole, from its first cell
+    while start > 0 && (new[start].is_empty() || old[start].is_empty()) {
+        start -= 1;
+    }
+    while end < new.len() && (new[end].is_empty() || old[end].is_empty()) {
+        end += 1;
+    }
+
+    print_cells(backend, frame, y, start..end)
+}
+
+/// Prints the cells `columns` of row `y`, switching reverse video on for
+/// the selected ones.
+fn print_cells<B: Backend>(
+    backend: &mut B,
+    frame: &Frame,
+    y: u16,
+    columns: Range<usize>,
+) -> std::io::Result<()> {
+    let cells = &frame.cells[y as usize];
+    let reversed = &frame.reversed[y as usize];
+    backend.move_to(columns.start as u16, y)?;
+    let mut start = columns.start;
+    while start < columns.end {
+        let end = (start..columns.end)
+            .find(|&x| reversed[x] != reversed[start])
+            .unwrap_or(columns.end);
+        let text: String = cells[start..end].concat();
+        if reversed[start] {
+            backend.set_reversed(true)?;
+            backend.print(&text)?;
+            backend.set_reversed(false)?;
+        } else {
+            backend.print(&text)?;
+        }
+        start = end;
+    }
+    Ok(())
+}
+
+/// Draws the editor contents on `backend` from scratch and places the cursor.
+pub fn draw<B: Backend>(
+    editor: &Editor,
+    backend: &mut B,
+    show_cursor: bool,
+) -> std::io::Result<()> {
+    Renderer::new().draw(editor, backend, show_cursor)
+}
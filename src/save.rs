@@ -0,0 +1,163 @@
+//! Crash-safe file writing.
+//!
+//! The new content goes to a temporary file in the same directory, which is
+//! synced and then renamed over the original. Until the rename the original
+//! is untouched, and the rename itself is atomic, so a crash or a full disk
+//! never leaves a half-written file behind.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use crate::error::{self, EditorError, WithPath};
+
+/// Most symbolic links followed before giving up on a loop.
+const MAX_SYMLINKS: usize = 40;
+
+/// How [`write_atomically_with`] treats the file beyond its content.
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Permissions of the written file, by default those of the file it
+    /// replaces or the platform default for a new one. Given, a read-only
+    /// file is replaced too since its own permissions do not matter.
+    pub permissions: Option<fs::Permissions>,
+    /// Does not wait for the data to reach the disk, for files which are
+    /// rewritten often and cheap to lose, like journals. The file is still
+    /// either old or new after a crash of the editor, only a crash of the
+    /// system may leave it empty.
+    pub skip_sync: bool,
+}
+
+/// Replaces the content of `path` with whatever `write` produces.
+///
+/// A symbolic link stays a link and its target is rewritten instead. The
+/// permissions of an existing file are kept, and so is its owner where the
+/// process is allowed to set it. A read-only file is not replaced, that is
+/// `EditorError::PermissionDenied`. Errors, including those of `write`, are
+/// about `path`.
+pub fn write_atomically<F>(path: &Path, write: F) -> error::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    write_atomically_with(path, &WriteOptions::default(), write)
+}
+
+/// Like [`write_atomically`], with the file treated as `options` ask.
+pub fn write_atomically_with<F>(path: &Path, options: &WriteOptions, write: F) -> error::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    let target = resolve_symlinks(path).with_path(path)?;
+    let directory = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let original = fs::metadata(&target).ok();
+    // the rename would get around the permissions, which only guard writes
+    let read_only = original
+        .as_ref()
+        .is_some_and(|original| original.permissions().readonly());
+    if read_only && options.permissions.is_none() {
+        return Err(EditorError::PermissionDenied(path.to_path_buf()));
+    }
+
+    let (temporary_path, temporary) = create_temporary(&directory, &target).with_path(path)?;
+    let result = (|| {
+        // set before anything is written, so no one can read along
+        let permissions = options
+            .permissions
+            .clone()
+            .or_else(|| original.as_ref().map(fs::Metadata::permissions));
+        if let Some(permissions) = permissions {
+            temporary.set_permissions(permissions)?;
+        }
+        if let Some(original) = &original {
+            copy_owner(&temporary, original);
+        }
+        let mut out = BufWriter::new(temporary);
+        write(&mut out)?;
+        let file = out.into_inner().map_err(|error| error.into_error())?;
+        if !options.skip_sync {
+            file.sync_all()?;
+        }
+        fs::rename(&temporary_path, &target)
+    })();
+    if let Err(error) = result {
+        let _ = fs::remove_file(&temporary_path);
+        return Err(error).with_path(path);
+    }
+
+    if !options.skip_sync {
+        sync_directory(&directory);
+    }
+    Ok(())
+}
+
+/// Follows symbolic links until a path which is not a link, the final
+/// target does not have to exist yet.
+fn resolve_symlinks(path: &Path) -> std::io::Result<PathBuf> {
+    let mut current = path.to_path_buf();
+    for _ in 0..MAX_SYMLINKS {
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&current)?;
+                current = match current.parent() {
+                    Some(parent) if link.is_relative() => parent.join(link),
+                    _ => link,
+                };
+            }
+            _ => return Ok(current),
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("too many levels of symbolic links in {}", path.display()),
+    ))
+}
+
+fn create_temporary(directory: &Path, target: &Path) -> std::io::Result<(PathBuf, File)> {
+    let name = target
+        .file_name()
+        .map_or_else(|| "file".into(), |name| name.to_string_lossy());
+    for attempt in 0.. {
+        let path = directory.join(format!(".{name}.{}.{attempt}.tmp", std::process::id()));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    unreachable!()
+}
+
+/// Permissions which let only the owner read and write, `None` where the
+/// platform has no such notion.
+pub fn private_permissions() -> Option<fs::Permissions> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        Some(fs::Permissions::from_mode(0o600))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+// changing the owner needs privileges, without them the file simply
+// belongs to whoever saved it
+#[cfg(unix)]
+fn copy_owner(file: &File, original: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    let _ = std::os::unix::fs::fchown(file, Some(original.uid()), Some(original.gid()));
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_file: &File, _original: &fs::Metadata) {}
+
+// makes the rename itself durable, not every platform can open a directory
+fn sync_directory(directory: &Path) {
+    if let Ok(directory) = File::open(directory) {
+        let _ = directory.sync_all();
+    }
+}
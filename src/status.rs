@@ -0,0 +1,44 @@
+//! The status bar on the bottom row of the screen.
+
+use crate::editor::Editor;
+use crate::total_lines;
+use crate::unicode;
+
+/// The status bar for `editor` on a screen `width` cells wide.
+///
+/// The file name, `[RO]` when it is read-only, a `[+]` while there are
+/// unsaved changes and the latest message are on the left, the cursor
+/// position, the number of lines, the encoding and the line endings on the
+/// right. When both do not fit, the right part is left out.
+pub fn status_line(editor: &Editor, width: usize) -> String {
+    let mut left = editor.file_name().to_string();
+    if editor.is_read_only() {
+        left.push_str(" [RO]");
+    }
+    if editor.is_modified() {
+        left.push_str(" [+]");
+    }
+    if let Some(message) = editor.message() {
+        left.push_str("  ");
+        left.push_str(message);
+    }
+
+    let (x, y) = editor.cursor();
+    let lines = total_lines(editor.buffer());
+    let right = format!(
+        "{}:{}  {} {}  {}  {}",
+        y + 1,
+        x + 1,
+        lines,
+        if lines == 1 { "line" } else { "lines" },
+        editor.encoding().name(),
+        editor.line_ending().name()
+    );
+
+    let used = unicode::display_width(&left) + unicode::display_width(&right);
+    if used < width {
+        format!("{left}{}{right}", " ".repeat(width - used))
+    } else {
+        left
+    }
+}
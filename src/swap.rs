@@ -0,0 +1,240 @@
+//! Swap files journaling unsaved changes.
+//!
+//! While the buffer has unsaved changes its text is written now and then to
+//! `.<name>.rtn-swp` next to the edited file, together with the id of the
+//! process writing it. A swap file whose process is gone was left behind by a
+//! crash and can be recovered the next time the file is opened. A file at that
+//! path written by another program is never overwritten.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::buffer::TextBuffer;
+use crate::error::{self, EditorError, WithPath};
+use crate::save;
+
+const MAGIC: &str = "rust_terminal_notepad swap 1";
+
+/// Lines of unchanged text shown around every change of a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Content of a swap file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapFile {
+    /// Process that wrote the swap file.
+    pub pid: u32,
+    /// The whole text of the buffer when it was journaled.
+    pub text: String,
+}
+
+impl SwapFile {
+    /// Whether the editor that wrote the swap file is not running any more.
+    ///
+    /// A swap file of this very process belongs to an editor that was dropped
+    /// without quitting, which is as good as a crash.
+    pub fn is_stale(&self) -> bool {
+        !owned_by_other_editor(self.pid)
+    }
+}
+
+/// Whether `pid` is another editor which is still running.
+fn owned_by_other_editor(pid: u32) -> bool {
+    pid != std::process::id() && process_running(pid)
+}
+
+#[cfg(target_os = "linux")]
+fn process_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+// without a cheap way to ask, every swap file is assumed to be left behind
+#[cfg(not(target_os = "linux"))]
+fn process_running(_pid: u32) -> bool {
+    false
+}
+
+/// Path of the swap file for `file`.
+pub fn swap_path(file: &Path) -> PathBuf {
+    let name = file
+        .file_name()
+        .map_or_else(|| "untitled".into(), |name| name.to_string_lossy());
+    file.with_file_name(format!(".{name}.rtn-swp"))
+}
+
+/// Journals `buffer` as the unsaved text of `file`.
+///
+/// Fails with `EditorError::Locked` while another editor that is still
+/// running journals the same file.
+pub fn write(file: &Path, buffer: &TextBuffer) -> error::Result<()> {
+    let path = swap_path(file);
+    match owner(&path) {
+        Owner::Foreign => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "swap file of another program, not overwritten",
+            ))
+            .with_path(&path);
+        }
+        Owner::Editor(Some(pid)) if owned_by_other_editor(pid) => {
+            return Err(EditorError::Locked {
+                path: file.to_path_buf(),
+                pid,
+            });
+        }
+        Owner::Nobody | Owner::Editor(_) => {}
+    }
+    // the journal is as private as the file it journals
+    let options = save::WriteOptions {
+        permissions: fs::metadata(file)
+            .ok()
+            .map(|metadata| metadata.permissions())
+            .or_else(save::private_permissions),
+        // journaled often and on the UI thread, a system crash is rare
+        skip_sync: true,
+    };
+    save::write_atomically_with(&path, &options, |out| {
+        writeln!(out, "{MAGIC}")?;
+        writeln!(out, "{} {}", std::process::id(), buffer.len())?;
+        for chunk in buffer.chunks() {
+            out.write_all(chunk.as_bytes())?;
+        }
+        Ok(())
+    })
+}
+
+/// Reads the swap file of `file`, `None` when there is none.
+pub fn read(file: &Path) -> error::Result<Option<SwapFile>> {
+    let path = swap_path(file);
+    let input = match fs::File::open(&path) {
+        Ok(input) => input,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error).with_path(&path),
+    };
+    read_from(&mut BufReader::new(input)).with_path(&path)
+}
+
+/// Who wrote whatever is at the swap file path.
+enum Owner {
+    Nobody,
+    /// An editor, with its process id unless the header is damaged.
+    Editor(Option<u32>),
+    /// Some other program, like another editor using the same name.
+    Foreign,
+}
+
+/// Finds out who wrote `path` from the header alone.
+fn owner(path: &Path) -> Owner {
+    let Ok(input) = fs::File::open(path) else {
+        return Owner::Nobody;
+    };
+    // room for the magic line and the header, whatever follows is not read
+    let mut input = BufReader::new(input).take(MAGIC.len() as u64 + 64);
+    let mut magic = String::new();
+    if input.read_line(&mut magic).is_err() || magic.trim_end() != MAGIC {
+        return Owner::Foreign;
+    }
+    let mut header = String::new();
+    let _ = input.read_line(&mut header);
+    Owner::Editor(header.split(' ').next().and_then(|pid| pid.parse().ok()))
+}
+
+fn read_from<R: BufRead>(input: &mut R) -> std::io::Result<Option<SwapFile>> {
+    let mut magic = String::new();
+    input.read_line(&mut magic)?;
+    if magic.trim_end() != MAGIC {
+        return Err(invalid("not a swap file"));
+    }
+    let mut header = String::new();
+    input.read_line(&mut header)?;
+    let (pid, len) = header
+        .trim_end()
+        .split_once(' ')
+        .ok_or_else(|| invalid("malformed swap header"))?;
+    let pid = pid.parse().map_err(|_| invalid("malformed pid"))?;
+    let len: usize = len.parse().map_err(|_| invalid("malformed length"))?;
+
+    // the length is only checked, it comes from the file and is not trusted
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    if text.len() != len {
+        return Err(invalid("truncated swap file"));
+    }
+    Ok(Some(SwapFile { pid, text }))
+}
+
+/// Deletes the swap file of `file`, if there is one.
+pub fn remove(file: &Path) -> error::Result<()> {
+    let path = swap_path(file);
+    match fs::remove_file(&path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error).with_path(&path),
+        _ => Ok(()),
+    }
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// One line of a diff between the saved and the recovered text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+impl DiffLine {
+    /// The line as it is shown, marked with `-`, `+` or a blank.
+    pub fn to_display(&self) -> String {
+        match self {
+            DiffLine::Same(line) => format!("  {line}"),
+            DiffLine::Removed(line) => format!("- {line}"),
+            DiffLine::Added(line) => format!("+ {line}"),
+        }
+    }
+}
+
+/// Lines that differ between `old` and `new`, with a few unchanged lines
+/// around them.
+///
+/// The lines both texts start and end with are matched, everything between
+/// them counts as removed and added. That is coarse, but a swap file rarely
+/// differs from the file in more than one place.
+pub fn diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.split('\n').collect();
+    let new: Vec<&str> = new.split('\n').collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if prefix == old.len() && prefix == new.len() {
+        return Vec::new();
+    }
+
+    let same = |line: &&str| DiffLine::Same(line.to_string());
+    let mut lines: Vec<DiffLine> = old[prefix.saturating_sub(DIFF_CONTEXT)..prefix]
+        .iter()
+        .map(same)
+        .collect();
+    lines.extend(
+        old[prefix..old.len() - suffix]
+            .iter()
+            .map(|line| DiffLine::Removed(line.to_string())),
+    );
+    lines.extend(
+        new[prefix..new.len() - suffix]
+            .iter()
+            .map(|line| DiffLine::Added(line.to_string())),
+    );
+    let after = old.len() - suffix;
+    lines.extend(
+        old[after..(after + DIFF_CONTEXT).min(old.len())]
+            .iter()
+            .map(same),
+    );
+    lines
+}
@@ -0,0 +1,75 @@
+//! Putting the real terminal into the state the editor needs and back.
+//!
+//! The terminal is restored whichever way the editor ends: `TerminalGuard`
+//! does it when dropped, on a normal exit as well as on an error returned
+//! with `?`, and the panic hook does it before the panic message is printed,
+//! so the message shows up on the normal screen instead of being lost with
+//! the alternate one.
+
+use std::io::stdout;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crossterm::{cursor, event, execute, terminal};
+
+// whether the terminal is set up for the editor, restoring twice would
+// print escape sequences after the shell took the terminal back
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Keeps the terminal in raw mode on the alternate screen while alive.
+#[derive(Debug)]
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    /// Enables raw mode, enters the alternate screen, hides the cursor and
+    /// asks for pasted text in one piece.
+    pub fn enter() -> std::io::Result<Self> {
+        // dropped on error, undoing whatever was done already
+        let guard = TerminalGuard { _private: () };
+        ACTIVE.store(true, Ordering::SeqCst);
+        terminal::enable_raw_mode()?;
+        execute!(
+            stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            event::EnableBracketedPaste
+        )?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Stops bracketed paste, leaves the alternate screen, shows the cursor and
+/// disables raw mode.
+///
+/// Does nothing when the terminal was never set up or is restored already.
+/// Errors are ignored, there is nothing better to do with a broken terminal
+/// than carry on with the next step.
+pub fn restore() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let _ = execute!(
+        stdout(),
+        event::DisableBracketedPaste,
+        terminal::LeaveAlternateScreen,
+        cursor::Show
+    );
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Restores the terminal before the panic message is printed.
+pub fn install_panic_hook() {
+    let report = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        report(info);
+    }));
+}
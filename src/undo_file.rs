@@ -0,0 +1,123 @@
+//! Undo history kept on disk between editing sessions.
+//!
+//! The history of a file is stored in the cache directory under a name derived
+//! from the file's canonical path. It also records a hash of the content it
+//! belongs to, a history whose file was changed by someone else is discarded.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::buffer::TextBuffer;
+use crate::error::{self, WithPath};
+use crate::history::History;
+use crate::save;
+
+const MAGIC: &str = "rust_terminal_notepad undo 1";
+
+/// Directory the undo files are kept in, `None` when there is no home directory.
+pub fn undo_dir() -> Option<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(cache.join("rust_terminal_notepad").join("undo"))
+}
+
+/// FNV-1a, stable between builds unlike the hasher of the standard library.
+fn fnv1a<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for &byte in part {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Hash of the whole text, used to tell whether a history still applies.
+pub fn content_hash(buffer: &TextBuffer) -> u64 {
+    fnv1a(buffer.chunks().map(str::as_bytes))
+}
+
+/// Path of the undo file for `file` inside `dir`.
+pub fn undo_file_path(dir: &Path, file: &Path) -> error::Result<PathBuf> {
+    let canonical = fs::canonicalize(file).with_path(file)?;
+    let key = fnv1a([canonical.to_string_lossy().as_bytes()]);
+    Ok(dir.join(format!("{key:016x}.undo")))
+}
+
+/// Stores `history` for `file`, whose content is now `buffer`.
+pub fn store(dir: &Path, file: &Path, buffer: &TextBuffer, history: &History) -> error::Result<()> {
+    let path = undo_file_path(dir, file)?;
+    if !history.can_undo() && !history.can_redo() {
+        return remove(&path).with_path(&path);
+    }
+    create_private_dir(dir).with_path(dir)?;
+
+    // the history holds deleted text, nobody else should read it
+    let canonical = fs::canonicalize(file).with_path(file)?;
+    let options = save::WriteOptions {
+        permissions: save::private_permissions(),
+        ..save::WriteOptions::default()
+    };
+    save::write_atomically_with(&path, &options, |out| {
+        writeln!(out, "{MAGIC}")?;
+        writeln!(out, "{}", canonical.to_string_lossy())?;
+        writeln!(out, "{:016x} {}", content_hash(buffer), buffer.len())?;
+        history.write_to(out)
+    })
+}
+
+/// Creates `dir` and its missing parents accessible to the owner only.
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+/// Loads the history stored for `file` if it was saved for exactly the text
+/// in `buffer`. A history that no longer matches or cannot be read is deleted.
+pub fn load(dir: &Path, file: &Path, buffer: &TextBuffer) -> Option<History> {
+    let path = undo_file_path(dir, file).ok()?;
+    let input = File::open(&path).ok()?;
+    match read(&mut BufReader::new(input), file, buffer) {
+        Ok(Some(history)) => Some(history),
+        _ => {
+            let _ = remove(&path);
+            None
+        }
+    }
+}
+
+fn read<R: BufRead>(
+    input: &mut R,
+    file: &Path,
+    buffer: &TextBuffer,
+) -> std::io::Result<Option<History>> {
+    let mut header = String::new();
+    for _ in 0..3 {
+        input.read_line(&mut header)?;
+    }
+    let expected = format!(
+        "{MAGIC}\n{}\n{:016x} {}\n",
+        fs::canonicalize(file)?.to_string_lossy(),
+        content_hash(buffer),
+        buffer.len()
+    );
+    if header != expected {
+        return Ok(None);
+    }
+    let history = History::read_from(input)?;
+    Ok(history.applies_to(buffer).then_some(history))
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,115 @@
+//! Grapheme cluster and display width helpers.
+//!
+//! A cursor column counts grapheme clusters, which is what the user sees as
+//! one character: `ą`, `e` + combining accent or a whole family emoji joined
+//! with zero-width joiners. Byte offsets are only ever derived from these
+//! clusters, so they always fall on char boundaries.
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Number of screen cells a tab is drawn with.
+pub const TAB_WIDTH: usize = 4;
+
+/// Number of grapheme clusters in `line`.
+pub fn grapheme_count(line: &str) -> usize {
+    line.graphemes(true).count()
+}
+
+/// Byte offset of grapheme `column` in `line`, the line length past its end.
+pub fn byte_offset(line: &str, column: usize) -> usize {
+    line.grapheme_indices(true)
+        .nth(column)
+        .map_or(line.len(), |(index, _)| index)
+}
+
+/// Column of the grapheme that starts at or contains byte `offset`.
+pub fn column_at_byte(line: &str, offset: usize) -> usize {
+    line.grapheme_indices(true)
+        .take_while(|(index, _)| *index < offset)
+        .count()
+}
+
+/// Number of screen cells taken by a single grapheme cluster.
+///
+/// East Asian wide characters and emoji take two cells, everything else one,
+/// including control and zero-width characters which are drawn as a
+/// placeholder so the cursor never lands inside an invisible character.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    if grapheme == "\t" {
+        TAB_WIDTH
+    } else if grapheme.chars().any(char::is_control) {
+        1
+    } else {
+        grapheme.width().clamp(1, 2)
+    }
+}
+
+/// What gets printed on the terminal for a grapheme cluster, so that it
+/// takes exactly `grapheme_width` cells.
+pub fn display_symbol(grapheme: &str) -> Cow<'_, str> {
+    if grapheme == "\t" {
+        Cow::Owned(" ".repeat(TAB_WIDTH))
+    } else if grapheme.chars().any(char::is_control) {
+        Cow::Borrowed("\u{fffd}")
+    } else if grapheme.width() == 0 {
+        Cow::Borrowed(" ")
+    } else {
+        Cow::Borrowed(grapheme)
+    }
+}
+
+/// Screen width of `text`.
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Screen width of the first `column` graphemes of `line`.
+pub fn width_before(line: &str, column: usize) -> usize {
+    line.graphemes(true).take(column).map(grapheme_width).sum()
+}
+
+/// Column of the last grapheme starting at or before screen cell `width`,
+/// used to keep the visual column when moving between lines.
+pub fn column_at_width(line: &str, width: usize) -> usize {
+    let mut used = 0;
+    for (column, grapheme) in line.graphemes(true).enumerate() {
+        used += grapheme_width(grapheme);
+        if used > width {
+            return column;
+        }
+    }
+    grapheme_count(line)
+}
+
+/// `line` as it is printed on the terminal.
+pub fn display_line(line: &str) -> String {
+    line.graphemes(true).map(display_symbol).collect()
+}
+
+/// The part of `line` printed from screen column `left` on, at most `width`
+/// cells. A wide character cut by either edge shows as blanks.
+pub fn display_slice(line: &str, left: usize, width: usize) -> String {
+    let right = left + width;
+    let mut slice = String::new();
+    let mut column = 0;
+    for grapheme in line.graphemes(true) {
+        let cells = grapheme_width(grapheme);
+        let (start, end) = (column, column + cells);
+        column = end;
+        if end <= left {
+            continue;
+        }
+        if start >= right {
+            break;
+        }
+        if start < left || end > right {
+            slice.push_str(&" ".repeat(end.min(right) - start.max(left)));
+        } else {
+            slice.push_str(&display_symbol(grapheme));
+        }
+    }
+    slice
+}
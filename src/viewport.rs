@@ -0,0 +1,85 @@
+//! The part of the text that fits on the screen.
+
+/// Top line and left screen column of the text shown, along with the size of
+/// the text area in cells.
+///
+/// It only scrolls when the cursor would leave it, and then only as far as
+/// needed, so moving around inside the visible part keeps the text still.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// First line of the buffer on the screen.
+    pub top: usize,
+    /// First row of the top line on the screen, when lines wrap.
+    pub top_row: usize,
+    /// First screen column of the lines on the screen.
+    pub left: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    pub fn new(width: usize, height: usize) -> Self {
+        Viewport {
+            top: 0,
+            top_row: 0,
+            left: 0,
+            width,
+            height,
+        }
+    }
+
+    /// Scrolls so that screen column `x` of line `y` is visible.
+    pub fn scroll_to(&mut self, x: usize, y: usize) {
+        // even a zero sized area shows the cursor's cell
+        let width = self.width.max(1);
+        let height = self.height.max(1);
+        if y < self.top {
+            self.top = y;
+        } else if y >= self.top + height {
+            self.top = y + 1 - height;
+        }
+        if x < self.left {
+            self.left = x;
+        } else if x >= self.left + width {
+            self.left = x + 1 - width;
+        }
+    }
+
+    /// Scrolls so that row `row` of line `y` is visible when lines wrap,
+    /// `rows_in` tells how many rows a line takes.
+    pub fn scroll_to_row(&mut self, y: usize, row: usize, rows_in: impl Fn(usize) -> usize) {
+        let height = self.height.max(1);
+        self.left = 0;
+        self.top_row = self.top_row.min(rows_in(self.top).saturating_sub(1));
+        if (y, row) < (self.top, self.top_row) {
+            self.top = y;
+            self.top_row = row;
+            return;
+        }
+        // every line takes at least one row, the lines this far up cannot be seen
+        if y >= self.top + height {
+            self.top = y + 1 - height;
+            self.top_row = 0;
+        }
+        let mut below = self.screen_row(y, row, &rows_in);
+        while below >= height {
+            if self.top_row + 1 < rows_in(self.top) {
+                self.top_row += 1;
+            } else {
+                self.top += 1;
+                self.top_row = 0;
+            }
+            below -= 1;
+        }
+    }
+
+    /// Screen row of row `row` of line `y`, which must not be above the
+    /// viewport, when lines wrap.
+    pub fn screen_row(&self, y: usize, row: usize, rows_in: impl Fn(usize) -> usize) -> usize {
+        if y == self.top {
+            return row - self.top_row;
+        }
+        let between: usize = (self.top + 1..y).map(&rows_in).sum();
+        rows_in(self.top) - self.top_row + between + row
+    }
+}
@@ -0,0 +1,130 @@
+//! Soft wrapping of long lines into several screen rows.
+//!
+//! A wrapped line is only split on the screen, the buffer keeps it whole.
+//! Every row after the first starts with `CONTINUATION_MARKER`, so a wrapped
+//! line can be told apart from lines which really are short.
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::unicode;
+
+/// Drawn at the start of every row that continues the line above.
+pub const CONTINUATION_MARKER: &str = "↪";
+
+/// How lines longer than the screen are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    /// One line per row, the viewport scrolls sideways.
+    Off,
+    /// Rows break after whatever grapheme fills them.
+    Anywhere,
+    /// Rows break after the last whitespace that fits, words too long for a
+    /// row break anywhere.
+    Words,
+}
+
+/// The rows `line` is shown in on a screen `width` cells wide, as ranges of
+/// grapheme columns.
+///
+/// There is always at least one row. When the last row is full, an empty one
+/// follows it, so the cursor at the end of the line has a cell to stand on.
+pub fn rows(line: &str, width: usize, wrap: Wrap) -> Vec<Range<usize>> {
+    let widths: Vec<(bool, usize)> = line
+        .graphemes(true)
+        .map(|grapheme| {
+            let blank = grapheme.chars().all(char::is_whitespace);
+            (blank, unicode::grapheme_width(grapheme))
+        })
+        .collect();
+    if wrap == Wrap::Off {
+        return std::iter::once(0..widths.len()).collect();
+    }
+
+    let mut rows = Vec::new();
+    let mut start = 0;
+    loop {
+        let available = row_width(width, rows.len());
+        let mut end = start;
+        let mut used = 0;
+        // a row holds at least one grapheme, however narrow the screen
+        while end < widths.len() && (end == start || used + widths[end].1 <= available) {
+            used += widths[end].1;
+            end += 1;
+        }
+        if end == widths.len() {
+            rows.push(start..end);
+            if used >= available {
+                rows.push(end..end);
+            }
+            return rows;
+        }
+        if wrap == Wrap::Words {
+            if let Some(blank) = (start + 1..end).rev().find(|&x| widths[x - 1].0) {
+                end = blank;
+            }
+        }
+        rows.push(start..end);
+        start = end;
+    }
+}
+
+/// Cells available for text in row `row` of a line, the marker takes one.
+fn row_width(width: usize, row: usize) -> usize {
+    if row == 0 {
+        width.max(1)
+    } else {
+        width.saturating_sub(1).max(1)
+    }
+}
+
+/// Row of `rows` column `x` is shown in, and the screen column of it.
+pub fn locate(rows: &[Range<usize>], line: &str, x: usize) -> (usize, usize) {
+    let row = rows.iter().rposition(|range| range.start <= x).unwrap_or(0);
+    let start = rows[row].start;
+    let text = &line[unicode::byte_offset(line, start)..];
+    (
+        row,
+        marker_width(row) + unicode::width_before(text, x - start),
+    )
+}
+
+/// Column of the grapheme shown at screen column `screen_x` of row `row`.
+/// Past the end of a row that is not the last, the column stays on its
+/// last grapheme.
+pub fn column_at(rows: &[Range<usize>], line: &str, row: usize, screen_x: usize) -> usize {
+    let range = rows[row].clone();
+    let text = row_text(line, &range);
+    let column =
+        range.start + unicode::column_at_width(text, screen_x.saturating_sub(marker_width(row)));
+    if row + 1 < rows.len() && column >= range.end {
+        range.end.saturating_sub(1).max(range.start)
+    } else {
+        column
+    }
+}
+
+/// The text of `line` in `range` of grapheme columns.
+pub fn row_text<'a>(line: &'a str, range: &Range<usize>) -> &'a str {
+    &line[unicode::byte_offset(line, range.start)..unicode::byte_offset(line, range.end)]
+}
+
+/// What is printed for row `row` of a line whose text in that row is `text`.
+pub fn display_row(text: &str, row: usize) -> String {
+    let text = unicode::display_line(text);
+    if row == 0 {
+        text
+    } else {
+        format!("{CONTINUATION_MARKER}{text}")
+    }
+}
+
+/// Cells taken by the marker in front of row `row`.
+pub fn marker_width(row: usize) -> usize {
+    if row == 0 {
+        0
+    } else {
+        unicode::display_width(CONTINUATION_MARKER)
+    }
+}
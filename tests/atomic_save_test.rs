@@ -0,0 +1,117 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+mod common;
+
+use common::scratch_dir;
+use rust_terminal_notepad::save::write_atomically;
+use rust_terminal_notepad::{Editor, EditorError};
+
+fn entries(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_save_replaces_content_without_leftovers() {
+    let dir = scratch_dir("save_replaces_content");
+    let file = dir.join("notes.txt");
+    fs::write(&file, "old content which is longer").unwrap();
+
+    write_atomically(&file, |out| out.write_all(b"new")).unwrap();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+    assert_eq!(entries(&dir), vec!["notes.txt"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_read_only_file_is_not_replaced() {
+    let dir = scratch_dir("save_read_only");
+    let file = dir.join("notes.txt");
+    fs::write(&file, "protected").unwrap();
+    let mut permissions = fs::metadata(&file).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&file, permissions).unwrap();
+
+    let error = write_atomically(&file, |out| out.write_all(b"new")).unwrap_err();
+    assert!(matches!(&error, EditorError::PermissionDenied(path) if *path == file));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "protected");
+    assert_eq!(entries(&dir), vec!["notes.txt"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_failed_write_keeps_the_original() {
+    let dir = scratch_dir("failed_write_keeps_original");
+    let file = dir.join("notes.txt");
+    fs::write(&file, "precious").unwrap();
+
+    let result = write_atomically(&file, |out| {
+        out.write_all(b"half of the new")?;
+        Err(std::io::Error::other("disk full"))
+    });
+
+    assert!(result.is_err());
+    assert_eq!(fs::read_to_string(&file).unwrap(), "precious");
+    assert_eq!(entries(&dir), vec!["notes.txt"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_save_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = scratch_dir("save_keeps_permissions");
+    let file = dir.join("script.sh");
+    fs::write(&file, "echo old").unwrap();
+    fs::set_permissions(&file, fs::Permissions::from_mode(0o751)).unwrap();
+
+    write_atomically(&file, |out| out.write_all(b"echo new")).unwrap();
+
+    let mode = fs::metadata(&file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o751);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_save_through_symlink_keeps_the_link() {
+    let dir = scratch_dir("save_through_symlink");
+    fs::create_dir(dir.join("real")).unwrap();
+    let target = dir.join("real").join("notes.txt");
+    let link = dir.join("link.txt");
+    fs::write(&target, "old").unwrap();
+    std::os::unix::fs::symlink("real/notes.txt", &link).unwrap();
+
+    let mut editor = Editor::open(link.to_str().unwrap()).unwrap();
+    editor.handle_event(crossterm::event::Event::Key(
+        crossterm::event::KeyCode::Char('!').into(),
+    ));
+    editor.save().unwrap();
+
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old!");
+    assert_eq!(entries(&dir.join("real")), vec!["notes.txt"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_save_creates_a_new_file() {
+    let dir = scratch_dir("save_creates_new_file");
+    let file = dir.join("fresh.txt");
+
+    write_atomically(&file, |out| out.write_all("zażółć".as_bytes())).unwrap();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "zażółć");
+    fs::remove_dir_all(&dir).unwrap();
+}
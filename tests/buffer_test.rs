@@ -0,0 +1,100 @@
+use rust_terminal_notepad::TextBuffer;
+
+// Small deterministic generator so the random edits are reproducible
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound.max(1)
+    }
+}
+
+fn char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn assert_lines_match(buffer: &TextBuffer, text: &str) {
+    let lines: Vec<&str> = text.split('\n').collect();
+    assert_eq!(buffer.line_count(), lines.len());
+    let mut start = 0;
+    for (y, line) in lines.iter().enumerate() {
+        assert_eq!(buffer.line_start(y), start);
+        assert_eq!(buffer.line_end(y), start + line.len());
+        assert_eq!(buffer.line(y), *line);
+        assert_eq!(buffer.line_of(start), y);
+        start += line.len() + 1;
+    }
+}
+
+#[test]
+fn test_buffer_from_str() {
+    let buffer = TextBuffer::from("some string\nsome line");
+    assert_eq!(buffer.len(), 21);
+    assert_eq!(buffer.to_string(), "some string\nsome line");
+    assert_lines_match(&buffer, "some string\nsome line");
+
+    let empty = TextBuffer::new();
+    assert!(empty.is_empty());
+    assert_eq!(empty.line_count(), 1);
+    assert_eq!(empty.line(0), "");
+}
+
+#[test]
+fn test_buffer_insert_and_remove() {
+    let mut buffer = TextBuffer::from("Hello World");
+    buffer.insert(5, ",");
+    buffer.insert(12, "!\nsecond line");
+    assert_eq!(buffer.to_string(), "Hello, World!\nsecond line");
+    assert_eq!(buffer.line(1), "second line");
+
+    buffer.remove(13..14);
+    assert_eq!(buffer.to_string(), "Hello, World!second line");
+    assert_eq!(buffer.line_count(), 1);
+
+    buffer.remove(0..buffer.len());
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn test_buffer_slice_across_chunks() {
+    let text = "0123456789\n".repeat(1000);
+    let buffer = TextBuffer::from(text.as_str());
+    assert!(buffer.chunks().count() > 1);
+    assert_eq!(buffer.slice(1000..5000), text[1000..5000]);
+    assert_eq!(buffer.line(777), "0123456789");
+    assert_eq!(buffer.line_of(7775), 706);
+    assert_eq!(buffer.line_count(), 1001);
+}
+
+#[test]
+fn test_buffer_matches_string_after_random_edits() {
+    let mut rng = Lcg(42);
+    let mut expected = "ząb\n€uro\n".repeat(300);
+    let mut buffer = TextBuffer::from(expected.as_str());
+    let pieces = ["a", "\n", "ł", "🦀", "line\nbreak", &"x".repeat(1500)];
+
+    for _ in 0..2000 {
+        if rng.next(3) == 0 && !expected.is_empty() {
+            let start = char_boundary(&expected, rng.next(expected.len()));
+            let end = char_boundary(&expected, (start + rng.next(40)).min(expected.len()));
+            buffer.remove(start..end);
+            expected.replace_range(start..end, "");
+        } else {
+            let at = char_boundary(&expected, rng.next(expected.len() + 1));
+            let piece = pieces[rng.next(pieces.len())];
+            buffer.insert(at, piece);
+            expected.insert_str(at, piece);
+        }
+        assert_eq!(buffer.len(), expected.len());
+    }
+
+    assert_eq!(buffer.to_string(), expected);
+    assert_lines_match(&buffer, &expected);
+}
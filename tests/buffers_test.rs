@@ -0,0 +1,74 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, type_text};
+use rust_terminal_notepad::{Action, Buffers, Editor};
+
+fn feed(buffers: &mut Buffers, text: &str) {
+    for event in type_text(text) {
+        assert_eq!(buffers.handle_event(event), Action::Continue);
+    }
+}
+
+fn three_buffers() -> Buffers {
+    Buffers::new(vec![
+        Editor::new("test_buffers_a.txt"),
+        Editor::new("test_buffers_b.txt"),
+        Editor::new("test_buffers_c.txt"),
+    ])
+}
+
+#[test]
+fn test_switching_buffers() {
+    let mut buffers = three_buffers();
+    feed(&mut buffers, "in a");
+
+    buffers.handle_event(key(KeyCode::PageDown, KeyModifiers::CONTROL));
+    assert_eq!(buffers.index(), 1);
+    assert_eq!(
+        buffers.current().message(),
+        Some("Buffer 2/3: test_buffers_b.txt")
+    );
+    feed(&mut buffers, "in b");
+
+    // going back from the first wraps around to the last
+    buffers.handle_event(key(KeyCode::PageUp, KeyModifiers::CONTROL));
+    buffers.handle_event(key(KeyCode::PageUp, KeyModifiers::CONTROL));
+    assert_eq!(buffers.index(), 2);
+
+    let texts: Vec<String> = buffers.editors().iter().map(Editor::text).collect();
+    assert_eq!(texts, vec!["in a", "in b", ""]);
+}
+
+#[test]
+fn test_quitting_closes_one_buffer() {
+    let mut buffers = three_buffers();
+    buffers.switch_to(2);
+
+    let action = buffers.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(action, Action::Continue);
+    assert_eq!(buffers.len(), 2);
+    assert_eq!(buffers.current().file_name(), "test_buffers_b.txt");
+
+    // unsaved changes still ask first
+    feed(&mut buffers, "x");
+    buffers.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(buffers.len(), 2);
+    buffers.handle_event(key(KeyCode::Char('n'), KeyModifiers::NONE));
+    assert_eq!(buffers.len(), 1);
+
+    // the last one quits the editor, and is kept for a look at it
+    let action = buffers.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(action, Action::Quit);
+    assert_eq!(buffers.current().file_name(), "test_buffers_a.txt");
+}
+
+#[test]
+fn test_resize_reaches_every_buffer() {
+    let mut buffers = three_buffers();
+    buffers.handle_event(crossterm::event::Event::Resize(30, 5));
+    for editor in buffers.editors() {
+        assert_eq!(editor.viewport().height, 4);
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, scratch_dir, type_text};
+use rust_terminal_notepad::cli::{self, CliError, Command, FileArg, Options};
+use rust_terminal_notepad::{
+    Action, Config, Editor, EditorError, Encoding, LineNumbers, WordChars, Wrap,
+};
+
+fn options(args: &[&str]) -> Options {
+    match cli::parse(args.iter().copied()) {
+        Ok(Command::Edit(options)) => options,
+        other => panic!("expected files to edit, got {other:?}"),
+    }
+}
+
+fn file(path: &str, position: Option<(usize, usize)>) -> FileArg {
+    FileArg {
+        path: Some(path.to_string()),
+        position,
+    }
+}
+
+#[test]
+fn test_parse_help_and_version() {
+    assert_eq!(cli::parse(["--help"]), Ok(Command::Help));
+    assert_eq!(cli::parse(["a.txt", "-h"]), Ok(Command::Help));
+    assert_eq!(cli::parse(["-V"]), Ok(Command::Version));
+    assert!(cli::HELP.contains("--readonly"));
+    assert!(cli::version().starts_with("rust_terminal_notepad "));
+}
+
+#[test]
+fn test_parse_files_and_positions() {
+    assert_eq!(options(&[]), Options::default());
+    assert_eq!(
+        options(&["+12", "a.txt", "b.txt:3", "c.txt:4:7", "-"]).files,
+        vec![
+            file("a.txt", Some((12, 1))),
+            file("b.txt", Some((3, 1))),
+            file("c.txt", Some((4, 7))),
+            FileArg {
+                path: None,
+                position: None,
+            },
+        ]
+    );
+    // what is not a position stays part of the name
+    assert_eq!(
+        options(&["notes:draft", "c:0"]).files,
+        vec![file("notes:draft", None), file("c:0", None)]
+    );
+    assert_eq!(
+        options(&["--", "-R", "+3"]).files,
+        vec![file("-R", None), file("+3", None)]
+    );
+}
+
+#[test]
+fn test_parse_options() {
+    let options = options(&["-R", "--encoding", "latin1", "--config=my.conf", "a.txt"]);
+    assert!(options.read_only);
+    assert_eq!(options.encoding, Encoding::Latin1);
+    assert_eq!(options.config, Some(PathBuf::from("my.conf")));
+    assert_eq!(options.files, vec![file("a.txt", None)]);
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = |args: &[&str]| cli::parse(args.iter().copied()).unwrap_err();
+    assert_eq!(
+        error(&["--bogus"]),
+        CliError::UnknownOption("--bogus".to_string())
+    );
+    assert_eq!(
+        error(&["--encoding"]).to_string(),
+        "option '--encoding' needs a value"
+    );
+    assert_eq!(
+        error(&["--encoding=koi8-r"]).to_string(),
+        "unsupported encoding 'koi8-r', use utf-8 or latin1"
+    );
+    assert_eq!(
+        error(&["+x", "a.txt"]).to_string(),
+        "invalid line number in '+x'"
+    );
+    assert_eq!(
+        error(&["+0", "a.txt"]),
+        CliError::InvalidLine("+0".to_string())
+    );
+    assert_eq!(
+        error(&["a.txt", "+3"]).to_string(),
+        "'+3' needs a file after it"
+    );
+    assert_eq!(error(&["-", "-"]), CliError::StdinTwice);
+}
+
+#[test]
+fn test_open_files_at_positions() {
+    let dir = scratch_dir("cli_positions");
+    let first = dir.join("first.txt");
+    let second = dir.join("second.txt");
+    fs::write(&first, "one\ntwo\nthree").unwrap();
+    fs::write(&second, "alpha\nbeta").unwrap();
+    let config = dir.join("config");
+    fs::write(&config, "").unwrap();
+
+    let options = options(&[
+        "--config",
+        config.to_str().unwrap(),
+        &format!("{}:2:3", first.display()),
+        // past the end of the text stays inside it
+        &format!("{}:9:9", second.display()),
+    ]);
+    let buffers = cli::open(&options, &mut Cursor::new("")).unwrap();
+
+    assert_eq!(buffers.len(), 2);
+    assert_eq!(buffers.editors()[0].cursor(), (2, 1));
+    assert_eq!(buffers.editors()[1].cursor(), (4, 1));
+    assert_eq!(buffers.current().file_name(), first.to_str().unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_open_standard_input() {
+    let dir = scratch_dir("cli_stdin");
+    let config = dir.join("config");
+    fs::write(&config, "").unwrap();
+    let options = options(&["--config", config.to_str().unwrap(), "+2", "-"]);
+
+    let buffers = cli::open(&options, &mut Cursor::new("piped\r\ntext\r\n")).unwrap();
+    let editor = buffers.current();
+    assert_eq!(editor.text(), "piped\ntext\n");
+    assert_eq!(editor.cursor(), (0, 1));
+    // the text is not in any file yet
+    assert!(editor.is_modified());
+    assert!(editor.file_name().starts_with("untitled"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_config_file() {
+    let dir = scratch_dir("cli_config");
+    let config = dir.join("config");
+    fs::write(&config, "# settings\n\nwrap = words\nline_numbers=hybrid\n").unwrap();
+    assert_eq!(
+        Config::load(&config).unwrap(),
+        Config {
+            wrap: Wrap::Words,
+            line_numbers: LineNumbers::Hybrid,
+            word_chars: WordChars::default(),
+        }
+    );
+
+    let options = options(&["--config", config.to_str().unwrap(), "-"]);
+    let buffers = cli::open(&options, &mut Cursor::new("")).unwrap();
+    assert_eq!(buffers.current().wrap(), Wrap::Words);
+    assert_eq!(buffers.current().line_numbers(), LineNumbers::Hybrid);
+
+    fs::write(&config, "wrap = words\ncolour = blue\n").unwrap();
+    let error = cli::open(&options, &mut Cursor::new("")).err().unwrap();
+    assert!(matches!(error, EditorError::Config { line: 2, .. }));
+    assert_eq!(
+        error.to_string(),
+        format!("{}:2: unknown setting 'colour'", config.display())
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_read_only_refuses_changes() {
+    let dir = scratch_dir("cli_read_only");
+    let file = dir.join("notes.txt");
+    fs::write(&file, "keep").unwrap();
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    editor.set_read_only(true);
+
+    for event in type_text("x\n") {
+        editor.handle_event(event);
+    }
+    editor.handle_event(key(KeyCode::Backspace, KeyModifiers::NONE));
+    assert_eq!(editor.text(), "keep");
+    assert!(!editor.is_modified());
+    assert_eq!(
+        editor.message(),
+        Some(format!("{} is opened read-only", file.display()).as_str())
+    );
+    assert!(matches!(editor.save(), Err(EditorError::ReadOnly(_))));
+
+    // there is nothing to save on the way out
+    let action = editor.handle_event(key(KeyCode::Char('q'), KeyModifiers::CONTROL));
+    assert_eq!(action, Action::Quit);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_latin1_round_trip() {
+    let dir = scratch_dir("cli_latin1");
+    let file = dir.join("old.txt");
+    fs::write(&file, b"caf\xe9").unwrap();
+    assert!(Editor::open(file.to_str().unwrap()).is_err());
+
+    let mut editor = Editor::open_with_encoding(file.to_str().unwrap(), Encoding::Latin1).unwrap();
+    assert_eq!(editor.text(), "café");
+    for event in type_text(" crème") {
+        editor.handle_event(event);
+    }
+    editor.save().unwrap();
+    assert_eq!(fs::read(&file).unwrap(), b"caf\xe9 cr\xe8me");
+
+    // what latin1 cannot hold is not written
+    for event in type_text(" €") {
+        editor.handle_event(event);
+    }
+    let error = editor.save().unwrap_err();
+    assert!(error
+        .to_string()
+        .ends_with("'€' cannot be written in ISO-8859-1"));
+    assert_eq!(fs::read(&file).unwrap(), b"caf\xe9 cr\xe8me");
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,185 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use mockall::predicate::eq;
+
+mod common;
+
+use common::{editor_with, key, press, scratch_dir};
+use rust_terminal_notepad::clipboard::{self, osc52, Tool};
+use rust_terminal_notepad::{Buffers, Clipboard, Editor, SystemClipboard};
+
+mockall::mock! {
+    Clip {}
+    impl Clipboard for Clip {
+        fn copy(&mut self, text: &str) -> io::Result<()>;
+        fn paste(&mut self) -> io::Result<Option<String>>;
+    }
+}
+
+fn select_word_before(editor: &mut Editor) {
+    press(
+        editor,
+        KeyCode::Left,
+        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+    );
+}
+
+/// Collects what is written to the terminal.
+#[derive(Clone, Default)]
+struct Terminal(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_cut_copy_and_paste() {
+    let mut editor = editor_with("test_clipboard.txt", "one two");
+    select_word_before(&mut editor);
+    press(&mut editor, KeyCode::Char('c'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "one two");
+
+    // pasting over the selection replaces it
+    press(&mut editor, KeyCode::Char('v'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "one two");
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Char('v'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "twoone two");
+
+    press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+    select_word_before(&mut editor);
+    press(&mut editor, KeyCode::Char('x'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "twoone ");
+    press(&mut editor, KeyCode::Char('z'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "twoone two");
+
+    // nothing selected leaves the clipboard alone
+    press(&mut editor, KeyCode::Char('x'), KeyModifiers::CONTROL);
+    assert_eq!(editor.message(), Some("Nothing is selected"));
+    press(&mut editor, KeyCode::Char('v'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "twoone twotwo");
+}
+
+#[test]
+fn test_editors_use_the_clipboard_given_to_them() {
+    let mut mock = MockClip::new();
+    mock.expect_copy()
+        .with(eq("world"))
+        .times(1)
+        .returning(|_| Err(io::Error::other("no display")));
+    mock.expect_paste()
+        .times(1)
+        .returning(|| Ok(Some("a\r\nb\rc".to_string())));
+    let mut editor = editor_with("test_clipboard.txt", "hello world");
+    editor.set_clipboard(clipboard::shared(mock));
+
+    // a cut that could not be copied deletes nothing
+    select_word_before(&mut editor);
+    press(&mut editor, KeyCode::Char('x'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello world");
+    assert_eq!(editor.message(), Some("Cannot copy: no display"));
+
+    press(&mut editor, KeyCode::Char('v'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello a\nb\nc");
+    assert_eq!(editor.cursor(), (1, 2));
+    press(&mut editor, KeyCode::Char('z'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello world");
+}
+
+#[test]
+fn test_buffers_share_a_clipboard() {
+    let mut buffers = Buffers::new(vec![
+        editor_with("test_clipboard.txt", "from the first"),
+        Editor::new("test_clipboard_second.txt"),
+    ]);
+    select_word_before(buffers.current_mut());
+    press(
+        buffers.current_mut(),
+        KeyCode::Char('c'),
+        KeyModifiers::CONTROL,
+    );
+    buffers.handle_event(key(KeyCode::PageDown, KeyModifiers::CONTROL));
+    press(
+        buffers.current_mut(),
+        KeyCode::Char('v'),
+        KeyModifiers::CONTROL,
+    );
+    assert_eq!(buffers.current().text(), "first");
+}
+
+#[test]
+fn test_system_clipboard() {
+    assert_eq!(osc52("hi"), "\x1b]52;c;aGk=\x07");
+    assert_eq!(osc52("zażółć"), "\x1b]52;c;emHFvMOzxYLEhw==\x07");
+
+    let dir = scratch_dir("clipboard_tool");
+    let file = dir.join("clipboard");
+    let path = file.to_str().unwrap();
+    let tool = Tool::new(&["sh", "-c", &format!("cat > '{path}'")], &["cat", path]);
+    let terminal = Terminal::default();
+    let mut clipboard = SystemClipboard::new(Some(Box::new(terminal.clone())), Some(tool));
+
+    clipboard.copy("copied").unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "copied");
+    assert_eq!(terminal.0.borrow().as_slice(), osc52("copied").as_bytes());
+    // what another program put on the clipboard is pasted
+    fs::write(&file, "from elsewhere").unwrap();
+    assert_eq!(
+        clipboard.paste().unwrap().as_deref(),
+        Some("from elsewhere")
+    );
+
+    // without a working tool the text copied here is still there
+    fs::remove_file(&file).unwrap();
+    assert_eq!(clipboard.paste().unwrap().as_deref(), Some("copied"));
+    let mut clipboard = SystemClipboard::new(None, None);
+    assert_eq!(clipboard.paste().unwrap(), None);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// A terminal that went away.
+struct ClosedTerminal;
+
+impl Write for ClosedTerminal {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_system_clipboard_failures_are_warnings() {
+    let tool = Tool::new(&["false"], &["false"]);
+    let mut clipboard = SystemClipboard::new(Some(Box::new(ClosedTerminal)), Some(tool));
+    clipboard.copy("kept").unwrap();
+    let warning = clipboard.take_warning().unwrap();
+    assert!(warning.starts_with("terminal: "));
+    assert!(warning.contains("false failed"));
+    assert_eq!(clipboard.take_warning(), None);
+    assert_eq!(clipboard.paste().unwrap().as_deref(), Some("kept"));
+
+    // cutting still deletes, the text can be pasted in the editor
+    let mut editor = editor_with("test_clipboard.txt", "hello world");
+    editor.set_clipboard(clipboard::shared(clipboard));
+    select_word_before(&mut editor);
+    press(&mut editor, KeyCode::Char('x'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello ");
+    assert!(editor
+        .message()
+        .is_some_and(|message| message.starts_with("Copied, but not to the system clipboard")));
+    press(&mut editor, KeyCode::Char('v'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello world");
+}
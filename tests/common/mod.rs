@@ -0,0 +1,80 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+use rust_terminal_notepad::render::Renderer;
+use rust_terminal_notepad::{Action, Editor, HeadlessBackend};
+
+pub fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+    Event::Key(KeyEvent {
+        code,
+        modifiers,
+        kind: KeyEventKind::Press,
+        state: KeyEventState::NONE,
+    })
+}
+
+/// Key presses typing `text`, line breaks become Enter.
+pub fn type_text(text: &str) -> Vec<Event> {
+    text.chars()
+        .map(|c| match c {
+            '\n' => key(KeyCode::Enter, KeyModifiers::NONE),
+            c if c.is_uppercase() => key(KeyCode::Char(c), KeyModifiers::SHIFT),
+            c => key(KeyCode::Char(c), KeyModifiers::NONE),
+        })
+        .collect()
+}
+
+pub fn press(editor: &mut Editor, code: KeyCode, modifiers: KeyModifiers) -> Action {
+    editor.handle_event(key(code, modifiers))
+}
+
+/// An editor for `file_name` with `text` typed into it, so the typing is in
+/// the undo history. Long texts are journaled in a swap file next to
+/// `file_name`, `loaded_editor` does not type them.
+pub fn editor_with(file_name: &str, text: &str) -> Editor {
+    editor_set_up_with(file_name, text, |_| {})
+}
+
+/// Like `editor_with`, with `set_up` applied before anything is typed.
+pub fn editor_set_up_with(file_name: &str, text: &str, set_up: impl FnOnce(&mut Editor)) -> Editor {
+    let mut editor = Editor::new(file_name);
+    set_up(&mut editor);
+    for event in type_text(text) {
+        editor.handle_event(event);
+    }
+    editor
+}
+
+/// An editor holding `text` as if it was opened with it, for a file named
+/// `name` in the temporary directory.
+pub fn loaded_editor(name: &str, text: &str) -> Editor {
+    let file = std::env::temp_dir().join(format!("rust_terminal_notepad_{name}"));
+    Editor::from_text(file.to_str().unwrap(), text)
+}
+
+/// The screen of `width` by `height` cells with `editor` drawn on it.
+pub fn screen_of(editor: &Editor, width: u16, height: u16) -> HeadlessBackend {
+    let mut screen = HeadlessBackend::new(width, height);
+    Renderer::new().draw(editor, &mut screen, true).unwrap();
+    screen
+}
+
+/// An empty directory of its own for a test, leftover files are easy to spot.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rust_terminal_notepad_{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `notes.txt` in a new `scratch_dir`, holding `content` if there is some.
+pub fn scratch_file(name: &str, content: Option<&str>) -> PathBuf {
+    let file = scratch_dir(name).join("notes.txt");
+    if let Some(content) = content {
+        fs::write(&file, content).unwrap();
+    }
+    file
+}
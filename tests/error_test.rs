@@ -0,0 +1,78 @@
+use std::fs::{self, File};
+
+mod common;
+
+use common::{scratch_dir, type_text};
+use rust_terminal_notepad::{Editor, EditorError, MAX_FILE_SIZE};
+
+#[test]
+fn test_invalid_utf8_is_reported() {
+    let dir = scratch_dir("error_invalid_utf8");
+    let file = dir.join("latin2.txt");
+    fs::write(&file, b"za\xbf\xf3\xb3\xe6").unwrap();
+
+    let error = Editor::open(file.to_str().unwrap()).err().unwrap();
+    assert!(matches!(&error, EditorError::InvalidEncoding(path) if *path == file));
+    assert_eq!(
+        error.to_string(),
+        format!("{} is not valid UTF-8 text", file.display())
+    );
+    // callers working with io::Result still learn what kind of error it was
+    let error = std::io::Error::from(error);
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_too_large_file_is_not_loaded() {
+    let dir = scratch_dir("error_too_large");
+    let file = dir.join("huge.log");
+    // sparse, nothing is really written
+    File::create(&file)
+        .unwrap()
+        .set_len(MAX_FILE_SIZE + 1)
+        .unwrap();
+
+    let error = Editor::open(file.to_str().unwrap()).err().unwrap();
+    assert!(matches!(error, EditorError::TooLarge { size, .. } if size == MAX_FILE_SIZE + 1));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "{} is too large (257 MiB, at most 256 MiB can be edited)",
+            file.display()
+        )
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_locked_file_is_reported_as_busy() {
+    let file = std::path::PathBuf::from("notes.txt");
+    let error = EditorError::Locked {
+        path: file.clone(),
+        pid: 42,
+    };
+    assert_eq!(error.path(), Some(file.as_path()));
+    assert_eq!(
+        error.to_string(),
+        "notes.txt is being edited in another editor (process 42)"
+    );
+    let error = std::io::Error::from(error);
+    assert_eq!(error.kind(), std::io::ErrorKind::ResourceBusy);
+}
+
+#[test]
+fn test_save_into_missing_directory() {
+    let dir = scratch_dir("error_save_error");
+    let file = dir.join("missing").join("notes.txt");
+    let mut editor = Editor::new(file.to_str().unwrap());
+    for event in type_text("text") {
+        editor.handle_event(event);
+    }
+
+    let error = editor.save().unwrap_err();
+    assert!(matches!(&error, EditorError::NotFound(path) if *path == file));
+    assert_eq!(error.path(), Some(file.as_path()));
+    assert!(editor.is_modified());
+    fs::remove_dir_all(&dir).unwrap();
+}
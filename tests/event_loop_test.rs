@@ -0,0 +1,120 @@
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, scratch_dir, type_text};
+use rust_terminal_notepad::editor::{MESSAGE_TIMEOUT, SWAP_INTERVAL};
+use rust_terminal_notepad::{
+    run, swap, Buffers, Editor, EventSource, HeadlessBackend, ScriptedEvents,
+};
+
+#[test]
+fn test_run_until_quit() {
+    let mut buffers = Buffers::from(Editor::new("test_run_until_quit.txt"));
+    buffers.resize(20, 3);
+    let mut script = type_text("hello");
+    script.push(key(KeyCode::Esc, KeyModifiers::NONE));
+    script.push(key(KeyCode::Char('n'), KeyModifiers::NONE));
+    // never read, the editor quits before
+    script.extend(type_text("ignored"));
+    let mut events = ScriptedEvents::new(script);
+    let mut screen = HeadlessBackend::new(20, 3);
+
+    run(&mut buffers, &mut events, &mut screen).unwrap();
+
+    assert_eq!(buffers.current().text(), "hello");
+    assert_eq!(screen.line(0), "hello");
+    assert_eq!(screen.line(2), "Save changes? (y/n/c");
+    assert!(events.read_event().unwrap().is_some());
+}
+
+#[test]
+fn test_run_ends_with_the_input() {
+    let mut buffers = Buffers::from(Editor::new("test_run_ends_with_the_input.txt"));
+    let mut events = ScriptedEvents::new(type_text("abc"));
+    let mut screen = HeadlessBackend::new(20, 3);
+
+    run(&mut buffers, &mut events, &mut screen).unwrap();
+    assert_eq!(buffers.current().text(), "abc");
+}
+
+/// Stays quiet for one poll, then hands out its events.
+struct SlowEvents {
+    waited: bool,
+    events: ScriptedEvents,
+}
+
+impl EventSource for SlowEvents {
+    fn poll(&mut self, timeout: Duration) -> std::io::Result<bool> {
+        if self.waited {
+            return Ok(true);
+        }
+        self.waited = true;
+        std::thread::sleep(timeout);
+        Ok(false)
+    }
+
+    fn read_event(&mut self) -> std::io::Result<Option<Event>> {
+        self.events.read_event()
+    }
+}
+
+#[test]
+fn test_cursor_blinks_while_waiting() {
+    let mut buffers = Buffers::from(Editor::new("test_cursor_blinks_while_waiting.txt"));
+    let mut events = SlowEvents {
+        waited: false,
+        events: ScriptedEvents::new(vec![key(KeyCode::Esc, KeyModifiers::NONE)]),
+    };
+    let mut screen = HeadlessBackend::new(20, 3);
+
+    let start = Instant::now();
+    run(&mut buffers, &mut events, &mut screen).unwrap();
+
+    // the only wait lasted until the cursor had to blink
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    assert!(!screen.cursor_visible());
+}
+
+#[test]
+fn test_messages_expire() {
+    let file = "test_messages_expire.txt";
+    let mut editor = Editor::new(file);
+    assert_eq!(editor.next_deadline(), None);
+
+    editor.handle_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    assert_eq!(editor.message(), Some("Saved 1 line"));
+    let deadline = editor.next_deadline().unwrap();
+    assert!(deadline <= Instant::now() + MESSAGE_TIMEOUT);
+
+    editor.tick(Instant::now());
+    assert_eq!(editor.message(), Some("Saved 1 line"));
+    editor.tick(deadline);
+    assert_eq!(editor.message(), None);
+    assert_eq!(editor.next_deadline(), None);
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_unsaved_edits_are_journaled_on_time() {
+    let dir = scratch_dir("journal_on_time");
+    let file = dir.join("notes.txt");
+    let mut editor = Editor::new(file.to_str().unwrap());
+
+    for event in type_text("a few words") {
+        editor.handle_event(event);
+    }
+    assert!(!swap::swap_path(&file).exists());
+    let deadline = editor.next_deadline().unwrap();
+    assert!(deadline <= Instant::now() + SWAP_INTERVAL);
+
+    editor.tick(deadline);
+    assert_eq!(swap::read(&file).unwrap().unwrap().text, "a few words");
+    assert_eq!(editor.next_deadline(), None);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
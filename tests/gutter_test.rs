@@ -0,0 +1,93 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{editor_set_up_with, key, screen_of};
+use rust_terminal_notepad::{Editor, LineNumbers, Wrap};
+
+fn editor_with(text: &str, width: u16, height: u16, line_numbers: LineNumbers) -> Editor {
+    editor_set_up_with("test_gutter.txt", text, |editor| {
+        editor.resize(width, height);
+        editor.set_line_numbers(line_numbers);
+    })
+}
+
+#[test]
+fn test_absolute_numbers_grow_with_the_text() {
+    let mut editor = editor_with("a\nb\nc\nd\ne\nf\ng\nh\ni", 12, 12, LineNumbers::Absolute);
+    let screen = screen_of(&editor, 12, 12);
+    assert_eq!(editor.gutter_width(), 2);
+    assert_eq!(&screen.lines()[..3], ["1 a", "2 b", "3 c"]);
+    assert_eq!(screen.line(8), "9 i");
+    assert_eq!(screen.cursor(), (3, 8));
+
+    // the tenth line needs another digit, the text moves right
+    editor.handle_event(key(KeyCode::Enter, KeyModifiers::NONE));
+    let screen = screen_of(&editor, 12, 12);
+    assert_eq!(editor.gutter_width(), 3);
+    assert_eq!(&screen.lines()[..2], [" 1 a", " 2 b"]);
+    assert_eq!(screen.line(9), "10");
+    assert_eq!(screen.cursor(), (3, 9));
+}
+
+#[test]
+fn test_relative_and_hybrid_numbers() {
+    let mut editor = editor_with("one\ntwo\nthree\nfour", 20, 6, LineNumbers::Relative);
+    editor.handle_event(key(KeyCode::Up, KeyModifiers::NONE));
+    editor.handle_event(key(KeyCode::Up, KeyModifiers::NONE));
+    let screen = screen_of(&editor, 20, 6);
+    assert_eq!(
+        &screen.lines()[..4],
+        ["1 one", "0 two", "1 three", "2 four"]
+    );
+
+    editor.set_line_numbers(LineNumbers::Hybrid);
+    let screen = screen_of(&editor, 20, 6);
+    assert_eq!(
+        &screen.lines()[..4],
+        ["1 one", "2 two", "1 three", "2 four"]
+    );
+    assert_eq!(screen.cursor(), (5, 1));
+}
+
+#[test]
+fn test_gutter_narrows_the_text_area() {
+    let mut editor = editor_with("abcdefghij", 8, 3, LineNumbers::Off);
+    assert_eq!(editor.viewport().width, 8);
+    assert_eq!(editor.viewport().left, 3);
+
+    editor.set_line_numbers(LineNumbers::Absolute);
+    assert_eq!(editor.viewport().width, 6);
+    assert_eq!(editor.viewport().left, 5);
+    let screen = screen_of(&editor, 8, 3);
+    assert_eq!(screen.line(0), "1 fghij");
+    assert_eq!(screen.cursor(), (7, 0));
+
+    // wrapped rows leave the gutter blank
+    editor.set_wrap(Wrap::Anywhere);
+    let screen = screen_of(&editor, 8, 3);
+    assert_eq!(&screen.lines()[..2], ["1 abcdef", "  ↪ghij"]);
+    assert_eq!(screen.cursor(), (7, 1));
+}
+
+#[test]
+fn test_alt_l_cycles_line_numbers() {
+    let mut editor = editor_with("text", 20, 3, LineNumbers::Off);
+    let modes: Vec<LineNumbers> = (0..4)
+        .map(|_| {
+            editor.handle_event(key(KeyCode::Char('l'), KeyModifiers::ALT));
+            editor.line_numbers()
+        })
+        .collect();
+    assert_eq!(
+        modes,
+        [
+            LineNumbers::Absolute,
+            LineNumbers::Relative,
+            LineNumbers::Hybrid,
+            LineNumbers::Off
+        ]
+    );
+    assert_eq!(editor.message(), Some("Line numbers: off"));
+    assert_eq!(editor.text(), "text");
+}
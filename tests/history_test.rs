@@ -0,0 +1,111 @@
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, type_text};
+use rust_terminal_notepad::history::{Edit, Grouping, MAX_UNDO_STEPS};
+use rust_terminal_notepad::{Editor, History, TextBuffer};
+
+fn undo() -> Event {
+    key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+}
+
+fn redo() -> Event {
+    key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+}
+
+fn feed(editor: &mut Editor, events: Vec<Event>) {
+    for event in events {
+        editor.handle_event(event);
+    }
+}
+
+#[test]
+fn test_typed_characters_are_one_undo_step() {
+    let mut editor = Editor::new("test_typed_characters.txt");
+    feed(&mut editor, type_text("hello world"));
+    assert_eq!(editor.history().undo_len(), 1);
+
+    feed(&mut editor, vec![undo()]);
+    assert_eq!(editor.text(), "");
+    assert_eq!(editor.cursor(), (0, 0));
+
+    feed(&mut editor, vec![redo()]);
+    assert_eq!(editor.text(), "hello world");
+    assert_eq!(editor.cursor(), (11, 0));
+}
+
+#[test]
+fn test_undo_redo_restores_text_and_cursor() {
+    let mut editor = Editor::new("test_undo_redo.txt");
+    feed(&mut editor, type_text("abc\ndef"));
+
+    feed(&mut editor, vec![undo()]);
+    assert_eq!(editor.text(), "abc\n");
+    assert_eq!(editor.cursor(), (0, 1));
+    feed(&mut editor, vec![undo()]);
+    assert_eq!(editor.text(), "abc");
+    assert_eq!(editor.cursor(), (3, 0));
+    feed(&mut editor, vec![undo(), undo()]);
+    assert_eq!(editor.text(), "");
+
+    feed(&mut editor, vec![redo(), redo(), redo(), redo()]);
+    assert_eq!(editor.text(), "abc\ndef");
+    assert_eq!(editor.cursor(), (3, 1));
+}
+
+#[test]
+fn test_cursor_movement_starts_a_new_step() {
+    let mut editor = Editor::new("test_cursor_movement_step.txt");
+    feed(&mut editor, type_text("ab"));
+    feed(&mut editor, vec![key(KeyCode::Left, KeyModifiers::NONE)]);
+    feed(&mut editor, type_text("XY"));
+    assert_eq!(editor.text(), "aXYb");
+
+    feed(&mut editor, vec![undo()]);
+    assert_eq!(editor.text(), "ab");
+    assert_eq!(editor.cursor(), (1, 0));
+}
+
+#[test]
+fn test_backspaces_are_grouped_and_reverted() {
+    let mut editor = Editor::new("test_backspaces_grouped.txt");
+    feed(&mut editor, type_text("one\ntwo"));
+    let backspace = key(KeyCode::Backspace, KeyModifiers::NONE);
+    feed(&mut editor, vec![backspace.clone(); 5]);
+    assert_eq!(editor.text(), "on");
+
+    // the Backspaces came after the typing and are a step of their own
+    feed(&mut editor, vec![undo()]);
+    assert_eq!(editor.text(), "one\ntwo");
+    assert_eq!(editor.cursor(), (3, 1));
+}
+
+#[test]
+fn test_new_edit_clears_redo() {
+    let mut editor = Editor::new("test_new_edit_clears_redo.txt");
+    feed(&mut editor, type_text("abc"));
+    feed(&mut editor, vec![undo()]);
+    feed(&mut editor, type_text("x"));
+    feed(&mut editor, vec![redo()]);
+    assert_eq!(editor.text(), "x");
+    assert!(!editor.history().can_redo());
+}
+
+#[test]
+fn test_history_is_bounded() {
+    let mut buffer = TextBuffer::new();
+    let mut history = History::new();
+    for i in 0..MAX_UNDO_STEPS + 500 {
+        let edit = Edit::Insert {
+            offset: i,
+            text: "x".to_string(),
+        };
+        buffer.insert(i, "x");
+        history.record(edit, Grouping::Single, (i, 0), (i + 1, 0));
+    }
+    assert_eq!(history.undo_len(), MAX_UNDO_STEPS);
+
+    while history.undo(&mut buffer).is_some() {}
+    assert_eq!(buffer.len(), 500);
+}
@@ -3,35 +3,20 @@ use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
 
-use rust_terminal_notepad::initialize_text_buffer;
+mod common;
 
-// event::read mock structure
-struct MockEventReader {
-    events: Vec<Event>,
-    current: usize,
-}
-
-impl MockEventReader {
-    fn new(events: Vec<Event>) -> Self {
-        MockEventReader { events, current: 0 }
-    }
+use common::key;
+use rust_terminal_notepad::{initialize_text_buffer, Action, Editor, EventSource, ScriptedEvents};
 
-    fn read(&mut self) -> std::io::Result<Event> {
-        if self.current < self.events.len() {
-            let event = self.events[self.current].clone();
-            self.current += 1;
-            Ok(event)
-        } else {
-            Ok(Event::Key(KeyEvent {
-                code: KeyCode::Esc,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: crossterm::event::KeyEventState::NONE,
-            }))
+// Feeds events into the editor the same way the binary does until it quits
+fn run_editor(editor: &mut Editor, events: &mut ScriptedEvents) -> std::io::Result<()> {
+    while let Some(event) = events.read_event()? {
+        if editor.handle_event(event) == Action::Quit {
+            break;
         }
     }
+    Ok(())
 }
 
 #[test]
@@ -122,46 +107,17 @@ fn test_basic_text_input() -> std::io::Result<()> {
         }),
         // Save and exit
         Event::Key(KeyEvent {
-            code: KeyCode::Char('c'),
+            code: KeyCode::Char('q'),
             modifiers: KeyModifiers::CONTROL,
             kind: KeyEventKind::Press,
             state: crossterm::event::KeyEventState::NONE,
         }),
     ];
 
-    let mut mock_reader = MockEventReader::new(events);
-
-    // Editor operation simulation
-    let inserted_text = Arc::new(Mutex::new(String::new()));
-
-    loop {
-        match mock_reader.read()? {
-            Event::Key(key_event) => {
-                if key_event.kind != KeyEventKind::Release {
-                    match key_event {
-                        event
-                            if event.code == KeyCode::Char('c')
-                                && event.modifiers == KeyModifiers::CONTROL =>
-                        {
-                            let text = inserted_text.lock().unwrap();
-                            fs::write(test_file, &*text)?;
-                            break;
-                        }
-                        event if event.code == KeyCode::Esc => {
-                            break;
-                        }
-                        event => {
-                            if let KeyCode::Char(c) = event.code {
-                                let mut text = inserted_text.lock().unwrap();
-                                text.push(c);
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
+    let mut scripted = ScriptedEvents::new(events);
+    let mut editor = Editor::new(test_file);
+
+    run_editor(&mut editor, &mut scripted)?;
 
     // Checking the results
     assert!(
@@ -229,45 +185,17 @@ fn test_backspace() -> std::io::Result<()> {
             state: crossterm::event::KeyEventState::NONE,
         }),
         Event::Key(KeyEvent {
-            code: KeyCode::Char('c'),
+            code: KeyCode::Char('q'),
             modifiers: KeyModifiers::CONTROL,
             kind: KeyEventKind::Press,
             state: crossterm::event::KeyEventState::NONE,
         }),
     ];
 
-    let mut mock_reader = MockEventReader::new(events);
-    let inserted_text = Arc::new(Mutex::new(String::new()));
-
-    loop {
-        match mock_reader.read()? {
-            Event::Key(key_event) => {
-                if key_event.kind != KeyEventKind::Release {
-                    match key_event {
-                        event
-                            if event.code == KeyCode::Char('c')
-                                && event.modifiers == KeyModifiers::CONTROL =>
-                        {
-                            let text = inserted_text.lock().unwrap();
-                            fs::write(test_file, &*text)?;
-                            break;
-                        }
-                        event if event.code == KeyCode::Backspace => {
-                            let mut text = inserted_text.lock().unwrap();
-                            text.pop();
-                        }
-                        event => {
-                            if let KeyCode::Char(c) = event.code {
-                                let mut text = inserted_text.lock().unwrap();
-                                text.push(c);
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
+    let mut scripted = ScriptedEvents::new(events);
+    let mut editor = Editor::new(test_file);
+
+    run_editor(&mut editor, &mut scripted)?;
 
     // Checking the results
     assert!(
@@ -371,45 +299,17 @@ fn test_multiline_input() -> std::io::Result<()> {
             state: crossterm::event::KeyEventState::NONE,
         }),
         Event::Key(KeyEvent {
-            code: KeyCode::Char('c'),
+            code: KeyCode::Char('q'),
             modifiers: KeyModifiers::CONTROL,
             kind: KeyEventKind::Press,
             state: crossterm::event::KeyEventState::NONE,
         }),
     ];
 
-    let mut mock_reader = MockEventReader::new(events);
-    let inserted_text = Arc::new(Mutex::new(String::new()));
-
-    loop {
-        match mock_reader.read()? {
-            Event::Key(key_event) => {
-                if key_event.kind != KeyEventKind::Release {
-                    match key_event {
-                        event
-                            if event.code == KeyCode::Char('c')
-                                && event.modifiers == KeyModifiers::CONTROL =>
-                        {
-                            let text = inserted_text.lock().unwrap();
-                            fs::write(test_file, &*text)?;
-                            break;
-                        }
-                        event if event.code == KeyCode::Enter => {
-                            let mut text = inserted_text.lock().unwrap();
-                            text.push('\n');
-                        }
-                        event => {
-                            if let KeyCode::Char(c) = event.code {
-                                let mut text = inserted_text.lock().unwrap();
-                                text.push(c);
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
+    let mut scripted = ScriptedEvents::new(events);
+    let mut editor = Editor::new(test_file);
+
+    run_editor(&mut editor, &mut scripted)?;
 
     // Checking the results
     assert!(
@@ -436,11 +336,11 @@ fn test_read_file() -> std::io::Result<()> {
     let some_input_text = b"Here is some text!";
     file.write_all(some_input_text)?;
 
-    let (inserted_text, cursor_position) = initialize_text_buffer(&test_file)?;
+    let cursor_position = initialize_text_buffer(test_file)?.cursor;
 
     assert_eq!(
         cursor_position,
-        (some_input_text.len() as u16, 0),
+        (some_input_text.len(), 0),
         "Cursor position calculated incorrectly"
     );
 
@@ -512,44 +412,17 @@ fn test_read_file() -> std::io::Result<()> {
             state: crossterm::event::KeyEventState::NONE,
         }),
         Event::Key(KeyEvent {
-            code: KeyCode::Char('c'),
+            code: KeyCode::Char('q'),
             modifiers: KeyModifiers::CONTROL,
             kind: KeyEventKind::Press,
             state: crossterm::event::KeyEventState::NONE,
         }),
     ];
 
-    let mut mock_reader = MockEventReader::new(events);
-
-    loop {
-        match mock_reader.read()? {
-            Event::Key(key_event) => {
-                if key_event.kind != KeyEventKind::Release {
-                    match key_event {
-                        event
-                            if event.code == KeyCode::Char('c')
-                                && event.modifiers == KeyModifiers::CONTROL =>
-                        {
-                            let text = inserted_text.lock().unwrap();
-                            fs::write(test_file, &*text)?;
-                            break;
-                        }
-                        event if event.code == KeyCode::Enter => {
-                            let mut text = inserted_text.lock().unwrap();
-                            text.push('\n');
-                        }
-                        event => {
-                            if let KeyCode::Char(c) = event.code {
-                                let mut text = inserted_text.lock().unwrap();
-                                text.push(c);
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
+    let mut scripted = ScriptedEvents::new(events);
+    let mut editor = Editor::open(test_file)?;
+
+    run_editor(&mut editor, &mut scripted)?;
 
     // Checking the results
     assert!(
@@ -624,4 +497,57 @@ fn test_total_lines() {
     // empty text test
     let empty_text = "";
     assert_eq!(total_lines(empty_text), 1); // One blank line
-}
\ No newline at end of file
+}
+#[test]
+fn test_editing_in_the_middle_of_text() {
+    let mut editor = Editor::new("test_editing_in_the_middle.txt");
+
+    let events = vec![
+        key(KeyCode::Char('a'), KeyModifiers::NONE),
+        key(KeyCode::Char('c'), KeyModifiers::NONE),
+        key(KeyCode::Left, KeyModifiers::NONE),
+        key(KeyCode::Char('B'), KeyModifiers::SHIFT),
+        key(KeyCode::Enter, KeyModifiers::NONE),
+        key(KeyCode::Backspace, KeyModifiers::NONE),
+        key(KeyCode::Backspace, KeyModifiers::NONE),
+        key(KeyCode::Char('b'), KeyModifiers::NONE),
+    ];
+    for event in events {
+        assert_eq!(editor.handle_event(event), Action::Continue);
+    }
+
+    assert_eq!(editor.text(), "abc");
+    assert_eq!(editor.cursor(), (2, 0));
+
+    // quitting asks what to do with the changes, "n" drops them
+    editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(
+        editor.handle_event(key(KeyCode::Char('n'), KeyModifiers::NONE)),
+        Action::Quit
+    );
+    assert!(!Path::new(editor.file_name()).exists());
+}
+
+#[test]
+fn test_line_helpers_on_text_buffer() {
+    use rust_terminal_notepad::{line_length, position_to_index, total_lines, TextBuffer};
+    let text = "some string\nsome line\n\nząb";
+    let mut buffer = TextBuffer::from(text);
+
+    // The indexed buffer answers exactly like the plain string
+    for y in 0..5 {
+        assert_eq!(line_length(&buffer, y), line_length(text, y));
+        for x in 0..13 {
+            assert_eq!(position_to_index(&buffer, (x, y)), position_to_index(text, (x, y)));
+        }
+    }
+    assert_eq!(total_lines(&buffer), 4);
+    assert_eq!(position_to_index(&buffer, (2, 3)), 26); // after the two-byte 'ą'
+
+    // and keeps answering after edits
+    buffer.insert(position_to_index(&buffer, (4, 1)), "\nnew");
+    assert_eq!(total_lines(&buffer), 5);
+    assert_eq!(line_length(&buffer, 1), 4);
+    assert_eq!(line_length(&buffer, 2), 8);
+    assert_eq!(position_to_index(&buffer, (0, 2)), 17);
+}
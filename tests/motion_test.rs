@@ -0,0 +1,172 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{loaded_editor, press};
+use rust_terminal_notepad::motion::{
+    paragraph_down, paragraph_up, sentence_left, sentence_right, word_left, word_right,
+};
+use rust_terminal_notepad::{Config, WordChars};
+
+/// Every position `motion` stops at from `start` until it stays in place.
+fn stops(
+    start: (usize, usize),
+    motion: impl Fn((usize, usize)) -> (usize, usize),
+) -> Vec<(usize, usize)> {
+    let mut stops = Vec::new();
+    let mut position = start;
+    loop {
+        let next = motion(position);
+        if next == position {
+            return stops;
+        }
+        stops.push(next);
+        position = next;
+    }
+}
+
+#[test]
+fn test_word_motions_stop_at_words_and_punctuation() {
+    let text = "let x_1 = foo(bar);\n  next";
+    let words = WordChars::default();
+    assert_eq!(
+        stops((0, 0), |p| word_right(text, p, &words)),
+        vec![
+            (4, 0),
+            (8, 0),
+            (10, 0),
+            (13, 0),
+            (14, 0),
+            (17, 0),
+            (19, 0),
+            (0, 1),
+            (2, 1),
+            (6, 1)
+        ]
+    );
+    assert_eq!(
+        stops((6, 1), |p| word_left(text, p, &words)),
+        vec![
+            (2, 1),
+            (0, 1),
+            (19, 0),
+            (17, 0),
+            (14, 0),
+            (13, 0),
+            (10, 0),
+            (8, 0),
+            (4, 0),
+            (0, 0)
+        ]
+    );
+}
+
+#[test]
+fn test_word_motions_are_unicode_aware() {
+    let words = WordChars::default();
+    // combining marks stay with their letter, columns count graphemes
+    let text = "zażółć gęślą jaźń, cafe\u{301} 日本語";
+    assert_eq!(word_right(text, (0, 0), &words), (7, 0));
+    assert_eq!(word_right(text, (7, 0), &words), (13, 0));
+    assert_eq!(word_right(text, (13, 0), &words), (17, 0));
+    assert_eq!(word_right(text, (17, 0), &words), (19, 0));
+    assert_eq!(word_right(text, (19, 0), &words), (24, 0));
+    assert_eq!(word_left(text, (27, 0), &words), (24, 0));
+    assert_eq!(word_left(text, (23, 0), &words), (19, 0));
+}
+
+#[test]
+fn test_word_chars_are_configurable() {
+    let text = "kebab-case_name here";
+    assert_eq!(word_right(text, (0, 0), &WordChars::default()), (5, 0));
+    assert_eq!(word_right(text, (0, 0), &WordChars::new("")), (5, 0));
+    assert_eq!(word_right(text, (6, 0), &WordChars::new("")), (10, 0));
+    assert_eq!(word_right(text, (0, 0), &WordChars::new("-_")), (16, 0));
+
+    let config = Config::parse("word_chars = -_\n").unwrap();
+    assert_eq!(config.word_chars, WordChars::new("-_"));
+    let mut editor = loaded_editor("test_motion.txt", text);
+    config.apply(&mut editor);
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Right, KeyModifiers::CONTROL);
+    assert_eq!(editor.cursor(), (16, 0));
+}
+
+#[test]
+fn test_word_deletions() {
+    let mut editor = loaded_editor("test_motion.txt", "one two, three");
+    press(&mut editor, KeyCode::Backspace, KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "one two, ");
+    press(&mut editor, KeyCode::Backspace, KeyModifiers::ALT);
+    assert_eq!(editor.text(), "one two");
+    assert_eq!(editor.cursor(), (7, 0));
+
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Delete, KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "two");
+    assert_eq!(editor.cursor(), (0, 0));
+
+    // the deletions before the move are undone together
+    press(&mut editor, KeyCode::Char('z'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "one two");
+    press(&mut editor, KeyCode::Char('z'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "one two, three");
+
+    // what many terminals send for Ctrl+Backspace
+    press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Char('h'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "one two, ");
+}
+
+#[test]
+fn test_word_deletions_join_lines() {
+    let mut editor = loaded_editor("test_motion.txt", "first\nsecond");
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Backspace, KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "firstsecond");
+    assert_eq!(editor.cursor(), (5, 0));
+    press(&mut editor, KeyCode::Delete, KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "first");
+}
+
+#[test]
+fn test_paragraph_motions() {
+    let text = "one\ntwo\n\n\nthree\n  \nfour\nfive";
+    assert_eq!(
+        stops((0, 0), |p| paragraph_down(text, p.1)),
+        vec![(0, 2), (0, 5), (4, 7)]
+    );
+    assert_eq!(
+        stops((4, 7), |p| paragraph_up(text, p.1)),
+        vec![(0, 5), (0, 3), (0, 0)]
+    );
+
+    let mut editor = loaded_editor("test_motion.txt", text);
+    press(&mut editor, KeyCode::Up, KeyModifiers::CONTROL);
+    assert_eq!(editor.cursor(), (0, 5));
+    press(&mut editor, KeyCode::Up, KeyModifiers::CONTROL);
+    press(&mut editor, KeyCode::Down, KeyModifiers::CONTROL);
+    assert_eq!(editor.cursor(), (0, 5));
+}
+
+#[test]
+fn test_sentence_motions() {
+    let text = "First one. Second (with \"quotes.\") here!\nStill second? Third\n\nNew paragraph";
+    assert_eq!(
+        stops((0, 0), |p| sentence_right(text, p)),
+        vec![(11, 0), (35, 0), (0, 1), (14, 1), (0, 3), (13, 3)]
+    );
+    assert_eq!(sentence_left(text, (13, 3)), (0, 3));
+    assert_eq!(sentence_left(text, (0, 3)), (14, 1));
+    assert_eq!(sentence_left(text, (5, 1)), (0, 1));
+    assert_eq!(sentence_left(text, (0, 1)), (35, 0));
+    assert_eq!(sentence_left(text, (11, 0)), (0, 0));
+
+    let mut editor = loaded_editor("test_motion.txt", text);
+    press(&mut editor, KeyCode::Char('a'), KeyModifiers::ALT);
+    assert_eq!(editor.cursor(), (0, 3));
+    press(&mut editor, KeyCode::Char('a'), KeyModifiers::ALT);
+    assert_eq!(editor.cursor(), (14, 1));
+    press(&mut editor, KeyCode::Char('e'), KeyModifiers::ALT);
+    assert_eq!(editor.cursor(), (0, 3));
+}
@@ -0,0 +1,136 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{loaded_editor, press};
+use rust_terminal_notepad::Wrap;
+
+fn numbered_lines(count: usize) -> String {
+    let lines: Vec<String> = (0..count).map(|n| format!("line {n}")).collect();
+    lines.join("\n")
+}
+
+#[test]
+fn test_home_and_end() {
+    let mut editor = loaded_editor("test_navigation.txt", "first\nzażółć 日本");
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+    assert_eq!(editor.cursor(), (0, 1));
+    press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+    // graphemes, not bytes
+    assert_eq!(editor.cursor(), (9, 1));
+
+    press(&mut editor, KeyCode::Up, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+    assert_eq!(editor.cursor(), (5, 0));
+    // Right stops at the end of the line, then goes on to the next one
+    press(&mut editor, KeyCode::Right, KeyModifiers::NONE);
+    assert_eq!(editor.cursor(), (0, 1));
+}
+
+#[test]
+fn test_start_and_end_of_the_text() {
+    let mut editor = loaded_editor("test_navigation.txt", &numbered_lines(50));
+    editor.resize(20, 6);
+
+    press(&mut editor, KeyCode::Home, KeyModifiers::CONTROL);
+    assert_eq!(editor.cursor(), (0, 0));
+    assert_eq!(editor.viewport().top, 0);
+
+    press(&mut editor, KeyCode::End, KeyModifiers::CONTROL);
+    assert_eq!(editor.cursor(), (7, 49));
+    assert_eq!(editor.viewport().top, 45);
+}
+
+#[test]
+fn test_page_down_and_up_scroll_by_the_viewport_height() {
+    let mut editor = loaded_editor("test_navigation.txt", &numbered_lines(20));
+    // five rows of text above the status bar
+    editor.resize(20, 6);
+    press(&mut editor, KeyCode::Home, KeyModifiers::CONTROL);
+    press(&mut editor, KeyCode::Down, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+
+    press(&mut editor, KeyCode::PageDown, KeyModifiers::NONE);
+    assert_eq!(editor.cursor(), (6, 6));
+    assert_eq!(editor.viewport().top, 5);
+
+    press(&mut editor, KeyCode::PageDown, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::PageDown, KeyModifiers::NONE);
+    // the cursor keeps its screen column
+    assert_eq!(editor.cursor(), (6, 16));
+    assert_eq!(editor.viewport().top, 15);
+
+    // the last page does not scroll past the end of the text
+    press(&mut editor, KeyCode::PageDown, KeyModifiers::NONE);
+    assert_eq!(editor.cursor(), (6, 19));
+    assert_eq!(editor.viewport().top, 15);
+
+    press(&mut editor, KeyCode::PageUp, KeyModifiers::NONE);
+    assert_eq!(editor.cursor(), (6, 14));
+    assert_eq!(editor.viewport().top, 10);
+    for _ in 0..5 {
+        press(&mut editor, KeyCode::PageUp, KeyModifiers::NONE);
+    }
+    assert_eq!(editor.cursor(), (6, 0));
+    assert_eq!(editor.viewport().top, 0);
+}
+
+#[test]
+fn test_page_down_moves_by_screen_rows_when_wrapping() {
+    // every line takes two rows of a 10 cells wide screen
+    let lines: Vec<String> = (0..10).map(|n| format!("{n}23456789abcdef")).collect();
+    let mut editor = loaded_editor("test_navigation.txt", &lines.join("\n"));
+    editor.resize(10, 5);
+    editor.set_wrap(Wrap::Anywhere);
+    press(&mut editor, KeyCode::Home, KeyModifiers::CONTROL);
+
+    // four rows of text are two lines
+    press(&mut editor, KeyCode::PageDown, KeyModifiers::NONE);
+    assert_eq!(editor.cursor().1, 2);
+    press(&mut editor, KeyCode::PageDown, KeyModifiers::NONE);
+    assert_eq!(editor.cursor().1, 4);
+    press(&mut editor, KeyCode::PageUp, KeyModifiers::NONE);
+    assert_eq!(editor.cursor().1, 2);
+}
+
+#[test]
+fn test_delete_forward() {
+    let mut editor = loaded_editor("test_navigation.txt", "abc\ne\u{301}f");
+    press(&mut editor, KeyCode::Up, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+
+    press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(editor.text(), "bc\ne\u{301}f");
+    assert_eq!(editor.cursor(), (0, 0));
+
+    // at the end of a line the next one is joined
+    press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(editor.text(), "bce\u{301}f");
+
+    // a whole grapheme goes at once
+    press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(editor.text(), "bcf");
+    assert_eq!(editor.cursor(), (2, 0));
+
+    // nothing after the end of the text
+    press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(editor.text(), "bcf");
+}
+
+#[test]
+fn test_consecutive_deletes_are_undone_together() {
+    let mut editor = loaded_editor("test_navigation.txt", "hello world");
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+    for _ in 0..6 {
+        press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+    }
+    assert_eq!(editor.text(), "world");
+
+    press(&mut editor, KeyCode::Char('z'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello world");
+    assert_eq!(editor.cursor(), (0, 0));
+    press(&mut editor, KeyCode::Char('y'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "world");
+}
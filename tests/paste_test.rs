@@ -0,0 +1,80 @@
+use std::fs;
+
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{editor_with, key};
+use rust_terminal_notepad::{Action, Editor, HeadlessBackend, LineEnding, Prompt, ScriptedEvents};
+
+#[test]
+fn test_paste_is_one_edit() {
+    let mut editor = editor_with("test_paste.txt", "start ");
+    let block: String = (0..1000).map(|n| format!("line {n}\r\n")).collect();
+
+    editor.handle_event(Event::Paste(block.clone()));
+    assert_eq!(
+        editor.text(),
+        format!("start {}", block.replace("\r\n", "\n"))
+    );
+    assert_eq!(editor.cursor(), (0, 1000));
+
+    // the typing before the paste is a step of its own
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    assert_eq!(editor.text(), "start ");
+    assert_eq!(editor.cursor(), (6, 0));
+    editor.handle_event(key(KeyCode::Char('y'), KeyModifiers::CONTROL));
+    assert_eq!(editor.text().lines().count(), 1000);
+}
+
+#[test]
+fn test_paste_replaces_the_selection() {
+    let mut editor = editor_with("test_paste.txt", "keep this");
+    editor.handle_event(key(
+        KeyCode::Left,
+        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+    ));
+    editor.handle_event(Event::Paste("that\rand more".to_string()));
+    assert_eq!(editor.text(), "keep that\nand more");
+    assert_eq!(editor.selection(), None);
+
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    assert_eq!(editor.text(), "keep this");
+}
+
+#[test]
+fn test_paste_waits_for_the_prompt() {
+    let mut editor = editor_with("test_paste.txt", "text");
+    editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(editor.prompt(), Some(Prompt::SaveChanges));
+
+    // pasting "y" must not answer the question
+    assert_eq!(
+        editor.handle_event(Event::Paste("y".to_string())),
+        Action::Continue
+    );
+    assert_eq!(editor.prompt(), Some(Prompt::SaveChanges));
+    assert_eq!(editor.text(), "text");
+}
+
+#[test]
+fn test_pasted_line_breaks_are_saved_with_the_file_line_endings() {
+    let file = std::env::temp_dir().join("rust_terminal_notepad_paste_crlf.txt");
+    fs::write(&file, "first\r\n").unwrap();
+    let editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.line_ending(), LineEnding::CrLf);
+
+    let mut events = ScriptedEvents::new(vec![
+        key(KeyCode::Char('v'), KeyModifiers::NONE),
+        Event::Paste("second\nthird\r\n".to_string()),
+        key(KeyCode::Char('q'), KeyModifiers::CONTROL),
+    ]);
+    let mut screen = HeadlessBackend::new(20, 5);
+    rust_terminal_notepad::run(&mut editor.into(), &mut events, &mut screen).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "first\r\nvsecond\r\nthird\r\n"
+    );
+    fs::remove_file(&file).unwrap();
+}
@@ -0,0 +1,245 @@
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, type_text};
+use rust_terminal_notepad::render::{self, Renderer};
+use rust_terminal_notepad::{Action, Editor, EventSource, HeadlessBackend, ScriptedEvents};
+
+// Handles and draws every scripted event, like one iteration of the main loop
+fn run_headless(editor: &mut Editor, events: &mut ScriptedEvents, screen: &mut HeadlessBackend) {
+    render::draw(editor, screen, true).unwrap();
+    while let Some(event) = events.read_event().unwrap() {
+        if editor.handle_event(event) != Action::Continue {
+            break;
+        }
+        render::draw(editor, screen, true).unwrap();
+    }
+}
+
+#[test]
+fn test_screen_shows_typed_lines() {
+    let mut editor = Editor::new("test_screen_shows_typed_lines.txt");
+    let mut events = ScriptedEvents::new(type_text("Hello\nWorld"));
+    let mut screen = HeadlessBackend::new(20, 4);
+
+    run_headless(&mut editor, &mut events, &mut screen);
+
+    assert_eq!(
+        screen.lines(),
+        vec!["Hello", "World", "", "test_screen_shows_ty"]
+    );
+    assert_eq!(screen.cursor(), (5, 1));
+    assert!(screen.cursor_visible());
+}
+
+#[test]
+fn test_screen_follows_editing() {
+    let mut editor = Editor::new("test_screen_follows_editing.txt");
+    let mut events = ScriptedEvents::new(type_text("one\ntwo"));
+    events.push(key(KeyCode::Up, KeyModifiers::NONE));
+    events.push(key(KeyCode::Backspace, KeyModifiers::NONE));
+    let mut screen = HeadlessBackend::new(20, 3);
+
+    run_headless(&mut editor, &mut events, &mut screen);
+
+    assert_eq!(screen.lines(), vec!["on", "two", "test_screen_follows_"]);
+    assert_eq!(screen.cell(1, 0).symbol, "n");
+    assert_eq!(screen.cursor(), (2, 0));
+}
+
+#[test]
+fn test_screen_clips_long_lines() {
+    let mut editor = Editor::new("test_screen_clips_long_lines.txt");
+    let mut events = ScriptedEvents::new(type_text("abcdefgh\nxy\nz"));
+    let mut screen = HeadlessBackend::new(5, 3);
+
+    run_headless(&mut editor, &mut events, &mut screen);
+
+    assert_eq!(screen.lines(), vec!["abcde", "xy", "test_"]);
+}
+
+#[test]
+fn test_viewport_follows_cursor_down_and_up() {
+    let mut editor = Editor::new("test_viewport_follows_cursor.txt");
+    editor.resize(10, 4);
+    let text: Vec<String> = (0..10).map(|n| format!("line {n}")).collect();
+    let mut events = ScriptedEvents::new(type_text(&text.join("\n")));
+    let mut screen = HeadlessBackend::new(10, 4);
+
+    // the bottom row is the message line, three rows are left for text
+    run_headless(&mut editor, &mut events, &mut screen);
+    assert_eq!(
+        screen.lines(),
+        vec!["line 7", "line 8", "line 9", "test_viewp"]
+    );
+    assert_eq!(screen.cursor(), (6, 2));
+    assert_eq!(editor.viewport().top, 7);
+
+    // moving inside the visible lines does not scroll
+    events.push(key(KeyCode::Up, KeyModifiers::NONE));
+    events.push(key(KeyCode::Up, KeyModifiers::NONE));
+    run_headless(&mut editor, &mut events, &mut screen);
+    assert_eq!(screen.lines()[0], "line 7");
+    assert_eq!(screen.cursor(), (6, 0));
+
+    // one line further up scrolls by exactly one line
+    events.push(key(KeyCode::Up, KeyModifiers::NONE));
+    run_headless(&mut editor, &mut events, &mut screen);
+    assert_eq!(
+        screen.lines(),
+        vec!["line 6", "line 7", "line 8", "test_viewp"]
+    );
+    assert_eq!(screen.cursor(), (6, 0));
+
+    // quitting without saving removes the swap file written while typing
+    events.push(key(KeyCode::Esc, KeyModifiers::NONE));
+    events.push(key(KeyCode::Char('n'), KeyModifiers::NONE));
+    run_headless(&mut editor, &mut events, &mut screen);
+    assert!(!std::path::Path::new(".test_viewport_follows_cursor.txt.rtn-swp").exists());
+}
+
+#[test]
+fn test_viewport_scrolls_long_lines_sideways() {
+    let mut editor = Editor::new("test_viewport_scrolls_sideways.txt");
+    editor.resize(6, 3);
+    let mut script = type_text("abcdefghij\nxy");
+    script.push(key(KeyCode::Up, KeyModifiers::NONE));
+    script.extend((0..8).map(|_| key(KeyCode::Right, KeyModifiers::NONE)));
+    let mut events = ScriptedEvents::new(script);
+    let mut screen = HeadlessBackend::new(6, 3);
+
+    run_headless(&mut editor, &mut events, &mut screen);
+
+    // the cursor past the last character still needs a cell
+    assert_eq!(editor.viewport().left, 5);
+    assert_eq!(screen.lines(), vec!["fghij", "", "test_v"]);
+    assert_eq!(screen.cursor(), (5, 0));
+
+    // going back to the start of the line scrolls all the way left
+    events.push(key(KeyCode::Down, KeyModifiers::NONE));
+    events.push(key(KeyCode::Left, KeyModifiers::NONE));
+    events.push(key(KeyCode::Left, KeyModifiers::NONE));
+    run_headless(&mut editor, &mut events, &mut screen);
+    assert_eq!(editor.cursor(), (0, 1));
+    assert_eq!(screen.lines(), vec!["abcdef", "xy", "test_v"]);
+    assert_eq!(screen.cursor(), (0, 1));
+}
+
+#[test]
+fn test_wide_character_cut_by_the_left_edge() {
+    let mut editor = Editor::new("test_wide_character_cut.txt");
+    editor.resize(4, 2);
+    let mut events = ScriptedEvents::new(type_text("a日本語"));
+    let mut screen = HeadlessBackend::new(4, 2);
+
+    run_headless(&mut editor, &mut events, &mut screen);
+
+    // the line is 7 cells wide, the cursor after it needs an 8th
+    assert_eq!(editor.viewport().left, 4);
+    assert_eq!(screen.line(0), " 語");
+    assert_eq!(screen.cursor(), (3, 0));
+}
+
+#[test]
+fn test_renderer_prints_only_changes() {
+    let mut editor = Editor::new("test_renderer_prints_only_changes.txt");
+    let mut events = ScriptedEvents::new(type_text("first\nsecond"));
+    while let Some(event) = events.read_event().unwrap() {
+        editor.handle_event(event);
+    }
+    let mut screen = HeadlessBackend::new(20, 4);
+    let mut renderer = Renderer::new();
+
+    renderer.draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(screen.clears(), 1);
+    assert_eq!(
+        screen.take_printed(),
+        vec![
+            (0, 0, "first".to_string()),
+            (0, 1, "second".to_string()),
+            (0, 3, "test_renderer_prints".to_string())
+        ]
+    );
+
+    // nothing changed, nothing printed
+    renderer.draw(&editor, &mut screen, false).unwrap();
+    assert_eq!(screen.take_printed(), vec![]);
+    assert!(!screen.cursor_visible());
+
+    editor.handle_event(key(KeyCode::Char('!'), KeyModifiers::NONE));
+    renderer.draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(screen.take_printed(), vec![(6, 1, "!".to_string())]);
+    assert_eq!(screen.clears(), 1);
+    assert_eq!(
+        screen.lines(),
+        vec!["first", "second!", "", "test_renderer_prints"]
+    );
+    assert_eq!(screen.cursor(), (7, 1));
+}
+
+#[test]
+fn test_renderer_matches_full_redraw() {
+    let mut editor = Editor::new("test_renderer_matches_full_redraw.txt");
+    let mut script = type_text("日本語 text\nab");
+    script.push(key(KeyCode::Up, KeyModifiers::NONE));
+    script.push(key(KeyCode::Backspace, KeyModifiers::NONE));
+    script.extend(type_text("x"));
+    script.push(key(KeyCode::Esc, KeyModifiers::NONE));
+    script.push(key(KeyCode::Char('c'), KeyModifiers::NONE));
+    script.push(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    script.push(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    let mut screen = HeadlessBackend::new(12, 3);
+    let mut renderer = Renderer::new();
+
+    for event in script {
+        editor.handle_event(event);
+        renderer.draw(&editor, &mut screen, true).unwrap();
+
+        let mut fresh = HeadlessBackend::new(12, 3);
+        render::draw(&editor, &mut fresh, true).unwrap();
+        assert_eq!(screen.lines(), fresh.lines());
+        assert_eq!(screen.cursor(), fresh.cursor());
+    }
+    assert_eq!(screen.clears(), 1);
+}
+
+#[test]
+fn test_resize_keeps_cursor_visible() {
+    let mut editor = Editor::new("test_resize_keeps_cursor_visible.txt");
+    editor.resize(20, 6);
+    let text: Vec<String> = (0..5).map(|n| format!("line {n}")).collect();
+    let mut script = type_text(&text.join("\n"));
+    script.push(Event::Resize(4, 3));
+    let mut events = ScriptedEvents::new(script);
+    let mut screen = HeadlessBackend::new(20, 6);
+    let mut renderer = Renderer::new();
+
+    while let Some(event) = events.read_event().unwrap() {
+        if let Event::Resize(width, height) = event {
+            screen.resize(width, height);
+        }
+        editor.handle_event(event);
+        renderer.draw(&editor, &mut screen, true).unwrap();
+    }
+
+    // two rows of text are left, the cursor after "line 4" needs column 6
+    assert_eq!(editor.viewport().top, 3);
+    assert_eq!(editor.viewport().left, 3);
+    assert_eq!(screen.lines(), vec!["e 3", "e 4", "test"]);
+    assert_eq!(screen.cursor(), (3, 1));
+    // the new size is painted from scratch
+    assert_eq!(screen.clears(), 2);
+
+    // growing again shows everything that fits
+    screen.resize(20, 6);
+    editor.handle_event(Event::Resize(20, 6));
+    renderer.draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(editor.viewport().left, 0);
+    assert_eq!(editor.viewport().top, 0);
+    assert_eq!(
+        screen.lines()[..5],
+        ["line 0", "line 1", "line 2", "line 3", "line 4"]
+    );
+    assert_eq!(screen.cursor(), (6, 4));
+}
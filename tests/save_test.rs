@@ -0,0 +1,147 @@
+use std::fs;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, type_text};
+use rust_terminal_notepad::{render, Action, Editor, HeadlessBackend, Prompt};
+
+fn feed(editor: &mut Editor, text: &str) {
+    for event in type_text(text) {
+        assert_eq!(editor.handle_event(event), Action::Continue);
+    }
+}
+
+#[test]
+fn test_ctrl_s_saves_and_keeps_editing() {
+    let file = "test_ctrl_s_saves.txt";
+    let mut editor = Editor::new(file);
+    feed(&mut editor, "draft");
+    assert!(editor.is_modified());
+
+    let action = editor.handle_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    assert_eq!(action, Action::Continue);
+    assert!(!editor.is_modified());
+    assert_eq!(fs::read_to_string(file).unwrap(), "draft");
+    assert_eq!(editor.message(), Some("Saved 1 line"));
+
+    // the message goes away with the next key and editing goes on
+    feed(&mut editor, "!");
+    assert_eq!(editor.message(), None);
+    assert!(editor.is_modified());
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_quit_without_changes_does_not_ask() {
+    let mut editor = Editor::new("test_quit_without_changes.txt");
+    let action = editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(action, Action::Quit);
+}
+
+#[test]
+fn test_quit_prompt_yes_saves() {
+    let file = "test_quit_prompt_yes.txt";
+    let mut editor = Editor::new(file);
+    feed(&mut editor, "keep me");
+
+    editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(editor.prompt(), Some(Prompt::SaveChanges));
+
+    // the question is shown on the bottom row
+    let mut screen = HeadlessBackend::new(40, 3);
+    render::draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(screen.line(2), "Save changes? (y/n/cancel)");
+
+    let action = editor.handle_event(key(KeyCode::Char('y'), KeyModifiers::NONE));
+    assert_eq!(action, Action::Quit);
+    assert_eq!(fs::read_to_string(file).unwrap(), "keep me");
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_quit_prompt_cancel_keeps_editing() {
+    let mut editor = Editor::new("test_quit_prompt_cancel.txt");
+    feed(&mut editor, "abc");
+
+    editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    // other keys do not answer the question and do not edit
+    editor.handle_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+    assert_eq!(editor.text(), "abc");
+    assert_eq!(editor.prompt(), Some(Prompt::SaveChanges));
+    // nor do shortcuts that happen to end in an answer
+    for answer in ['n', 'y'] {
+        let action = editor.handle_event(key(KeyCode::Char(answer), KeyModifiers::CONTROL));
+        assert_eq!(action, Action::Continue);
+    }
+    assert_eq!(editor.prompt(), Some(Prompt::SaveChanges));
+    assert!(!std::path::Path::new("test_quit_prompt_cancel.txt").exists());
+
+    let action = editor.handle_event(key(KeyCode::Char('c'), KeyModifiers::NONE));
+    assert_eq!(action, Action::Continue);
+    assert_eq!(editor.prompt(), None);
+    assert!(editor.is_modified());
+
+    editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(editor.prompt(), None);
+}
+
+#[test]
+fn test_failed_save_is_reported_and_keeps_the_editor_open() {
+    let mut editor = Editor::new("no_such_directory/test_failed_save.txt");
+    feed(&mut editor, "text");
+
+    let action = editor.handle_event(key(KeyCode::Char('q'), KeyModifiers::CONTROL));
+    assert_eq!(action, Action::Continue);
+    assert!(editor.is_modified());
+    assert_eq!(
+        editor.message(),
+        Some("Cannot save: no_such_directory/test_failed_save.txt: no such file or directory")
+    );
+}
+
+#[test]
+fn test_undoing_back_to_the_saved_text_is_unmodified() {
+    let file = "test_undo_to_saved_text.txt";
+    let mut editor = Editor::new(file);
+    let undo = key(KeyCode::Char('z'), KeyModifiers::CONTROL);
+    let redo = key(KeyCode::Char('y'), KeyModifiers::CONTROL);
+    feed(&mut editor, "saved");
+    editor.handle_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    feed(&mut editor, " and more");
+    assert!(editor.is_modified());
+
+    editor.handle_event(undo.clone());
+    assert_eq!(editor.text(), "saved");
+    assert!(!editor.is_modified());
+    // and further back it differs from the file again
+    editor.handle_event(undo);
+    assert!(editor.is_modified());
+    editor.handle_event(redo.clone());
+    assert!(!editor.is_modified());
+    editor.handle_event(redo);
+    assert!(editor.is_modified());
+
+    // a different change where the saved text was undone cannot lead back
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    feed(&mut editor, "x");
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    assert_eq!(editor.text(), "");
+    assert!(editor.is_modified());
+
+    // Esc with nothing to save quits right away
+    editor.handle_event(key(KeyCode::Char('y'), KeyModifiers::CONTROL));
+    editor.handle_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    feed(&mut editor, "!");
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    assert_eq!(
+        editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE)),
+        Action::Quit
+    );
+    fs::remove_file(file).unwrap();
+}
@@ -0,0 +1,178 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{editor_with, press, type_text};
+use rust_terminal_notepad::render::{self, Renderer};
+use rust_terminal_notepad::{Action, Backend, HeadlessBackend, Wrap};
+
+/// Columns of row `y` drawn in reverse video.
+fn reversed(screen: &HeadlessBackend, y: u16) -> Vec<u16> {
+    let (width, _) = screen.size().unwrap();
+    (0..width).filter(|&x| screen.cell(x, y).reversed).collect()
+}
+
+#[test]
+fn test_shift_motions_extend_the_selection() {
+    let mut editor = editor_with("test_selection.txt", "first line\nsecond line");
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Right, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Right, KeyModifiers::SHIFT);
+    assert_eq!(editor.selection(), Some(((0, 1), (2, 1))));
+    assert_eq!(editor.selected_text().as_deref(), Some("se"));
+
+    // the anchor stays where the selection started
+    press(&mut editor, KeyCode::Up, KeyModifiers::SHIFT);
+    assert_eq!(editor.selection(), Some(((2, 0), (0, 1))));
+    assert_eq!(editor.selected_text().as_deref(), Some("rst line\n"));
+    press(&mut editor, KeyCode::Home, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Down, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::End, KeyModifiers::SHIFT);
+    assert_eq!(editor.selected_text().as_deref(), Some("second line"));
+
+    // back to where it started selects nothing
+    press(&mut editor, KeyCode::Home, KeyModifiers::SHIFT);
+    assert_eq!(editor.selection(), None);
+
+    press(&mut editor, KeyCode::End, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Left, KeyModifiers::NONE);
+    assert_eq!(editor.selection(), None);
+    assert_eq!(editor.cursor(), (10, 1));
+}
+
+#[test]
+fn test_shift_word_motions_and_select_all() {
+    let mut editor = editor_with("test_selection.txt", "one two three");
+    press(
+        &mut editor,
+        KeyCode::Left,
+        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+    );
+    press(
+        &mut editor,
+        KeyCode::Left,
+        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+    );
+    assert_eq!(editor.selected_text().as_deref(), Some("two three"));
+
+    press(&mut editor, KeyCode::Char('a'), KeyModifiers::CONTROL);
+    assert_eq!(editor.selected_text().as_deref(), Some("one two three"));
+    assert_eq!(editor.cursor(), (13, 0));
+
+    // Esc drops the selection before it quits
+    assert_eq!(
+        press(&mut editor, KeyCode::Esc, KeyModifiers::NONE),
+        Action::Continue
+    );
+    assert_eq!(editor.selection(), None);
+}
+
+#[test]
+fn test_shift_ctrl_home_and_end_select_to_the_ends() {
+    let mut editor = editor_with("test_selection.txt", "first\nsecond\nthird");
+    editor.go_to(3, 1);
+    let shift_ctrl = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+    press(&mut editor, KeyCode::End, shift_ctrl);
+    assert_eq!(editor.selected_text().as_deref(), Some("ond\nthird"));
+    assert_eq!(editor.cursor(), (5, 2));
+
+    press(&mut editor, KeyCode::Home, shift_ctrl);
+    assert_eq!(editor.selected_text().as_deref(), Some("first\nsec"));
+    assert_eq!(editor.cursor(), (0, 0));
+
+    // without Shift they only move
+    press(&mut editor, KeyCode::End, KeyModifiers::CONTROL);
+    assert_eq!(editor.selection(), None);
+    assert_eq!(editor.cursor(), (5, 2));
+}
+
+#[test]
+fn test_typing_replaces_the_selection() {
+    let mut editor = editor_with("test_selection.txt", "hello world");
+    press(
+        &mut editor,
+        KeyCode::Left,
+        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+    );
+    for event in type_text("there") {
+        editor.handle_event(event);
+    }
+    assert_eq!(editor.text(), "hello there");
+    assert_eq!(editor.selection(), None);
+
+    press(&mut editor, KeyCode::Char('a'), KeyModifiers::CONTROL);
+    press(&mut editor, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(editor.text(), "\n");
+
+    // replacing is undone in one step, the typing after it in another
+    press(&mut editor, KeyCode::Char('z'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello there");
+    press(&mut editor, KeyCode::Char('z'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello t");
+    press(&mut editor, KeyCode::Char('z'), KeyModifiers::CONTROL);
+    assert_eq!(editor.text(), "hello world");
+}
+
+#[test]
+fn test_deleting_the_selection() {
+    let mut editor = editor_with("test_selection.txt", "abc\ndef\nghi");
+    press(&mut editor, KeyCode::Up, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Left, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Up, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Backspace, KeyModifiers::NONE);
+    assert_eq!(editor.text(), "ab\nghi");
+    assert_eq!(editor.cursor(), (2, 0));
+
+    press(&mut editor, KeyCode::Right, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Right, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(editor.text(), "abhi");
+
+    press(&mut editor, KeyCode::Home, KeyModifiers::SHIFT);
+    editor.set_read_only(true);
+    press(&mut editor, KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(editor.text(), "abhi");
+}
+
+#[test]
+fn test_selection_is_drawn_in_reverse_video() {
+    let mut editor = editor_with("test_selection.txt", "zażółć\n日本\nend");
+    editor.resize(20, 5);
+    editor.go_to(5, 0);
+    press(&mut editor, KeyCode::Down, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Down, KeyModifiers::SHIFT);
+    press(&mut editor, KeyCode::Left, KeyModifiers::SHIFT);
+    let mut screen = HeadlessBackend::new(20, 5);
+    let mut renderer = Renderer::new();
+    renderer.draw(&editor, &mut screen, true).unwrap();
+
+    assert_eq!(editor.selected_text().as_deref(), Some("ć\n日本\nen"));
+    // the selected line breaks take a cell after their lines
+    assert_eq!(reversed(&screen, 0), vec![5, 6]);
+    assert_eq!(reversed(&screen, 1), vec![0, 1, 2, 3, 4]);
+    assert_eq!(reversed(&screen, 2), vec![0, 1]);
+    assert_eq!(screen.lines()[..3], ["zażółć", "日本", "end"]);
+
+    // only what changed is drawn again
+    press(&mut editor, KeyCode::Right, KeyModifiers::NONE);
+    renderer.draw(&editor, &mut screen, true).unwrap();
+    assert!((0..3).all(|y| reversed(&screen, y).is_empty()));
+    assert_eq!(screen.clears(), 1);
+}
+
+#[test]
+fn test_selection_in_wrapped_rows() {
+    let mut editor = editor_with("test_selection.txt", "abcdefghij");
+    editor.resize(6, 4);
+    editor.set_wrap(Wrap::Anywhere);
+    press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::Right, KeyModifiers::NONE);
+    press(&mut editor, KeyCode::End, KeyModifiers::SHIFT);
+    let mut screen = HeadlessBackend::new(6, 4);
+    render::draw(&editor, &mut screen, true).unwrap();
+
+    assert_eq!(screen.lines()[..2], ["abcdef", "↪ghij"]);
+    assert_eq!(reversed(&screen, 0), vec![1, 2, 3, 4, 5]);
+    // the marker is not part of the text
+    assert_eq!(reversed(&screen, 1), vec![1, 2, 3, 4]);
+}
@@ -0,0 +1,106 @@
+use std::fs;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, type_text};
+use rust_terminal_notepad::render::Renderer;
+use rust_terminal_notepad::{Editor, HeadlessBackend, LineEnding};
+
+fn feed(editor: &mut Editor, text: &str) {
+    for event in type_text(text) {
+        editor.handle_event(event);
+    }
+}
+
+#[test]
+fn test_status_bar_shows_file_and_position() {
+    let file = "test_status_bar_shows_file.txt";
+    let mut editor = Editor::new(file);
+    editor.resize(60, 4);
+    let mut screen = HeadlessBackend::new(60, 4);
+    let mut renderer = Renderer::new();
+
+    renderer.draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(
+        screen.line(3),
+        "test_status_bar_shows_file.txt        1:1  1 line  UTF-8  LF"
+    );
+
+    feed(&mut editor, "one\ntwö");
+    renderer.draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(
+        screen.line(3),
+        "test_status_bar_shows_file.txt [+]   2:4  2 lines  UTF-8  LF"
+    );
+
+    // the message sits next to the file name until the next key, the
+    // position makes room for it when both do not fit
+    editor.handle_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    renderer.draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(
+        screen.line(3),
+        "test_status_bar_shows_file.txt  Saved 2 lines"
+    );
+    editor.handle_event(key(KeyCode::Left, KeyModifiers::NONE));
+    renderer.draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(
+        screen.line(3),
+        "test_status_bar_shows_file.txt       2:3  2 lines  UTF-8  LF"
+    );
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_crlf_files_keep_their_line_endings() {
+    let file = "test_crlf_files_keep_line_endings.txt";
+    fs::write(file, "first\r\nsecond\r\n").unwrap();
+
+    let mut editor = Editor::open(file).unwrap();
+    assert_eq!(editor.line_ending(), LineEnding::CrLf);
+    // the buffer only ever holds '\n'
+    assert_eq!(editor.text(), "first\nsecond\n");
+
+    feed(&mut editor, "third");
+    editor.handle_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    assert_eq!(
+        fs::read_to_string(file).unwrap(),
+        "first\r\nsecond\r\nthird"
+    );
+
+    editor.resize(80, 3);
+    let mut screen = HeadlessBackend::new(80, 3);
+    Renderer::new().draw(&editor, &mut screen, true).unwrap();
+    assert!(screen.line(2).ends_with("3:6  3 lines  UTF-8  CRLF"));
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_line_ending_detection() {
+    assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("a\nb\n"), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("a\r\nb\r\n"), LineEnding::CrLf);
+    // mixed files go with the majority
+    assert_eq!(LineEnding::detect("a\r\nb\nc\r\n"), LineEnding::CrLf);
+    assert_eq!(LineEnding::detect("a\r\nb\nc\n"), LineEnding::Lf);
+
+    assert!(LineEnding::is_mixed("a\r\nb\n"));
+    assert!(!LineEnding::is_mixed("a\r\nb\r\n"));
+    assert!(!LineEnding::is_mixed("a\nb"));
+}
+
+#[test]
+fn test_mixed_line_endings_are_warned_about() {
+    let file = "test_mixed_line_endings.txt";
+    fs::write(file, "first\r\nsecond\nthird\n").unwrap();
+
+    let mut editor = Editor::open(file).unwrap();
+    assert_eq!(editor.message(), Some("Mixed line endings, saving as LF"));
+    editor.handle_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    assert_eq!(fs::read_to_string(file).unwrap(), "first\nsecond\nthird\n");
+
+    fs::remove_file(file).unwrap();
+}
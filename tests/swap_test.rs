@@ -0,0 +1,302 @@
+use std::fs;
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, scratch_file, type_text};
+use rust_terminal_notepad::editor::{SWAP_EDITS, SWAP_SCALE};
+use rust_terminal_notepad::swap::{self, DiffLine};
+use rust_terminal_notepad::{render, Action, Editor, HeadlessBackend, Prompt};
+
+fn feed(editor: &mut Editor, text: &str) {
+    for event in type_text(text) {
+        assert_eq!(editor.handle_event(event), Action::Continue);
+    }
+}
+
+/// Types `text` and leaves the editor without quitting, like a killed process.
+fn crash_while_typing(file: &Path, text: &str) {
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    feed(&mut editor, text);
+    editor.write_swap().unwrap();
+}
+
+#[test]
+fn test_edits_are_journaled_until_quitting() {
+    let file = scratch_file("swap_journaled", None);
+    let mut editor = Editor::new(file.to_str().unwrap());
+    feed(&mut editor, &"a".repeat(SWAP_EDITS - 1));
+    assert!(!swap::swap_path(&file).exists());
+
+    feed(&mut editor, "b");
+    let swap_file = swap::read(&file).unwrap().unwrap();
+    assert_eq!(swap_file.pid, std::process::id());
+    assert_eq!(swap_file.text, editor.text());
+
+    // dropping the changes on purpose drops the swap file too
+    editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    let action = editor.handle_event(key(KeyCode::Char('n'), KeyModifiers::NONE));
+    assert_eq!(action, Action::Quit);
+    assert!(!swap::swap_path(&file).exists());
+    assert!(!file.exists());
+}
+
+#[test]
+fn test_large_text_is_journaled_less_often() {
+    let file = scratch_file("swap_large", None);
+    let text = "a line of text\n".repeat(SWAP_SCALE / 15 + 1);
+    let mut editor = Editor::from_text(file.to_str().unwrap(), &text);
+    // the loaded text counts as the first edit
+    feed(&mut editor, &"a".repeat(SWAP_EDITS));
+    assert!(!swap::swap_path(&file).exists());
+
+    feed(&mut editor, &"a".repeat(SWAP_EDITS - 1));
+    assert!(swap::read(&file).unwrap().unwrap().text == editor.text());
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_recover_after_crash() {
+    let file = scratch_file("swap_recover", Some("first line\n"));
+    crash_while_typing(&file, "second line");
+
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.prompt(), Some(Prompt::Recover));
+    assert_eq!(editor.text(), "first line\n");
+
+    // the diff replaces the text until the question is answered
+    editor.handle_event(key(KeyCode::Char('d'), KeyModifiers::NONE));
+    let mut screen = HeadlessBackend::new(60, 5);
+    render::draw(&editor, &mut screen, true).unwrap();
+    assert_eq!(
+        screen.lines(),
+        vec![
+            "  first line",
+            "-",
+            "+ second line",
+            "",
+            "Unsaved changes found: recover, diff or discard? (r/d/x)",
+        ]
+    );
+    assert!(!screen.cursor_visible());
+
+    editor.handle_event(key(KeyCode::Char('r'), KeyModifiers::NONE));
+    assert_eq!(editor.prompt(), None);
+    assert_eq!(editor.text(), "first line\nsecond line");
+    assert_eq!(editor.cursor(), (11, 1));
+    assert!(editor.is_modified());
+
+    // the recovery is a single undo step
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+    assert_eq!(editor.text(), "first line\n");
+    editor.handle_event(key(KeyCode::Char('y'), KeyModifiers::CONTROL));
+
+    editor.handle_event(key(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "first line\nsecond line"
+    );
+    assert!(!swap::swap_path(&file).exists());
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_discard_recovery() {
+    let file = scratch_file("swap_discard", Some("saved"));
+    crash_while_typing(&file, " and lost");
+
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.prompt(), Some(Prompt::Recover));
+    // other keys do not answer the question
+    feed(&mut editor, "q");
+    assert_eq!(editor.prompt(), Some(Prompt::Recover));
+    // neither does cutting with Ctrl+X
+    editor.handle_event(key(KeyCode::Char('x'), KeyModifiers::CONTROL));
+    assert_eq!(editor.prompt(), Some(Prompt::Recover));
+    assert!(swap::swap_path(&file).exists());
+
+    editor.handle_event(key(KeyCode::Char('X'), KeyModifiers::SHIFT));
+    assert_eq!(editor.prompt(), None);
+    assert_eq!(editor.text(), "saved");
+    assert!(!editor.is_modified());
+    assert!(!swap::swap_path(&file).exists());
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_swap_file_without_changes_is_cleaned_up() {
+    let file = scratch_file("swap_unchanged", Some("same"));
+    crash_while_typing(&file, "");
+
+    let editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.prompt(), None);
+    assert!(!swap::swap_path(&file).exists());
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_emergency_dump_after_panic() {
+    let file = scratch_file("swap_panic", Some("kept"));
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.emergency_dump().unwrap(), None);
+
+    feed(&mut editor, " and typed");
+    assert_eq!(
+        editor.emergency_dump().unwrap(),
+        Some(swap::swap_path(&file))
+    );
+
+    // the next start offers what was typed
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.prompt(), Some(Prompt::Recover));
+    editor.handle_event(key(KeyCode::Char('r'), KeyModifiers::NONE));
+    assert_eq!(editor.text(), "kept and typed");
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_diff_shows_changes_with_context() {
+    let old = "1\n2\n3\n4\n5\n6\n7\n8\n9";
+    let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9";
+    assert_eq!(
+        swap::diff(old, new),
+        vec![
+            DiffLine::Same("2".to_string()),
+            DiffLine::Same("3".to_string()),
+            DiffLine::Same("4".to_string()),
+            DiffLine::Removed("5".to_string()),
+            DiffLine::Added("five".to_string()),
+            DiffLine::Same("6".to_string()),
+            DiffLine::Same("7".to_string()),
+            DiffLine::Same("8".to_string()),
+        ]
+    );
+    assert!(swap::diff(old, old).is_empty());
+}
+
+#[test]
+fn test_swap_file_of_another_program_is_not_overwritten() {
+    let file = scratch_file("swap_foreign", Some("text"));
+    let swap = swap::swap_path(&file);
+    assert_eq!(swap.file_name().unwrap(), ".notes.txt.rtn-swp");
+    fs::write(&swap, "b0VIM 9.0 recovery data").unwrap();
+
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.prompt(), None);
+    feed(&mut editor, &"a".repeat(SWAP_EDITS));
+    assert_eq!(
+        fs::read_to_string(&swap).unwrap(),
+        "b0VIM 9.0 recovery data"
+    );
+    assert!(editor
+        .message()
+        .is_some_and(|message| message.starts_with("Cannot write swap file")));
+
+    // quitting leaves it alone too
+    editor.handle_event(key(KeyCode::Esc, KeyModifiers::NONE));
+    editor.handle_event(key(KeyCode::Char('n'), KeyModifiers::NONE));
+    assert!(swap.exists());
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_swap_file_is_as_private_as_the_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    let file = scratch_file("swap_permissions", Some("secret"));
+    fs::set_permissions(&file, fs::Permissions::from_mode(0o640)).unwrap();
+    crash_while_typing(&file, "more ");
+    assert_eq!(mode(&swap::swap_path(&file)), 0o640);
+
+    // a file not saved yet has no permissions to copy
+    let new_file = file.with_file_name("new.txt");
+    crash_while_typing(&new_file, "secret");
+    assert_eq!(mode(&swap::swap_path(&new_file)), 0o600);
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_swap_file_of_a_running_editor_is_offered_with_a_warning() {
+    let file = scratch_file("swap_running", Some("saved"));
+    // process 1 is always running, or a crashed editor's id was reused
+    fs::write(
+        swap::swap_path(&file),
+        "rust_terminal_notepad swap 1\n1 7\nchanged",
+    )
+    .unwrap();
+
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.prompt(), Some(Prompt::RecoverInUse));
+    assert!(Prompt::RecoverInUse.question().starts_with("Warning"));
+    editor.handle_event(key(KeyCode::Char('d'), KeyModifiers::NONE));
+    assert!(editor.recovery_diff().is_some());
+    // nothing happens to the swap file until it is recovered or discarded
+    assert!(swap::swap_path(&file).exists());
+
+    editor.handle_event(key(KeyCode::Char('r'), KeyModifiers::NONE));
+    assert_eq!(editor.text(), "changed");
+    assert_eq!(editor.prompt(), None);
+    // the other editor keeps its journal
+    assert_eq!(swap::read(&file).unwrap().unwrap().pid, 1);
+    assert_eq!(
+        editor.message(),
+        Some(
+            format!(
+                "Cannot write swap file: {} is being edited in another editor (process 1)",
+                file.display()
+            )
+            .as_str()
+        )
+    );
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_swap_file_of_a_running_editor_is_never_removed() {
+    let file = scratch_file("swap_running_kept", Some("saved"));
+    let swap = swap::swap_path(&file);
+    fs::write(&swap, "rust_terminal_notepad swap 1\n1 7\nchanged").unwrap();
+
+    let mut editor = Editor::open(file.to_str().unwrap()).unwrap();
+    editor.handle_event(key(KeyCode::Char('x'), KeyModifiers::NONE));
+    assert_eq!(editor.prompt(), None);
+    assert!(swap.exists());
+    // editing does not overwrite it either
+    feed(&mut editor, &"a".repeat(SWAP_EDITS));
+    assert!(editor
+        .message()
+        .is_some_and(|message| message.contains("process 1")));
+    assert!(fs::read_to_string(&swap).unwrap().ends_with("changed"));
+
+    // nothing to recover, yet the other editor is still worth a warning
+    fs::write(&swap, "rust_terminal_notepad swap 1\n1 5\nsaved").unwrap();
+    let editor = Editor::open(file.to_str().unwrap()).unwrap();
+    assert_eq!(editor.prompt(), None);
+    assert!(editor
+        .message()
+        .is_some_and(|message| message.starts_with("Warning: ")));
+    assert!(swap.exists());
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_corrupt_swap_file_is_ignored() {
+    let file = scratch_file("swap_corrupt", Some("saved"));
+    for header in [format!("0 {}", usize::MAX), "0 100".to_string()] {
+        fs::write(
+            swap::swap_path(&file),
+            format!("rust_terminal_notepad swap 1\n{header}\nshort"),
+        )
+        .unwrap();
+        assert!(swap::read(&file).is_err());
+        let editor = Editor::open(file.to_str().unwrap()).unwrap();
+        assert_eq!(editor.prompt(), None);
+    }
+    fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, scratch_dir, type_text};
+use rust_terminal_notepad::{undo_file, Editor};
+
+// Every test of this binary keeps its undo files in the same scratch directory
+fn scratch_undo_dir() -> PathBuf {
+    std::env::temp_dir().join("rust_terminal_notepad_undo_file_test")
+}
+
+fn open(file: &str) -> Editor {
+    let mut editor = Editor::open(file).unwrap();
+    editor.set_undo_dir(Some(scratch_undo_dir()));
+    editor
+}
+
+fn edit_and_save(file: &str, text: &str) -> Editor {
+    let mut editor = open(file);
+    for event in type_text(text) {
+        editor.handle_event(event);
+    }
+    editor.save().unwrap();
+    editor
+}
+
+#[test]
+fn test_undo_history_survives_reopening() {
+    let file = "test_undo_history_survives.txt";
+    fs::write(file, "first").unwrap();
+
+    edit_and_save(file, " second\nthird");
+    let mut editor = open(file);
+    assert!(editor.history().can_undo());
+
+    let undo = key(KeyCode::Char('z'), KeyModifiers::CONTROL);
+    editor.handle_event(undo.clone());
+    assert_eq!(editor.text(), "first second\n");
+    editor.handle_event(undo.clone());
+    editor.handle_event(undo);
+    assert_eq!(editor.text(), "first");
+    assert_eq!(editor.cursor(), (5, 0));
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_undo_history_is_discarded_after_external_change() {
+    let file = "test_undo_history_discarded.txt";
+    fs::write(file, "text").unwrap();
+
+    edit_and_save(file, " more");
+    let undo_path = undo_file::undo_file_path(&scratch_undo_dir(), Path::new(file)).unwrap();
+    assert!(undo_path.exists());
+
+    // someone else rewrote the file in the meantime
+    fs::write(file, "completely different").unwrap();
+    assert!(!open(file).history().can_undo());
+    assert!(!undo_path.exists());
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_corrupted_undo_file_is_discarded() {
+    let file = "test_corrupted_undo_file.txt";
+    fs::write(file, "").unwrap();
+
+    let editor = edit_and_save(file, "abc");
+    let undo_path = undo_file::undo_file_path(&scratch_undo_dir(), Path::new(file)).unwrap();
+    let stored = fs::read_to_string(&undo_path).unwrap();
+    fs::write(&undo_path, &stored[..stored.len() - 3]).unwrap();
+
+    let reopened = open(file);
+    assert_eq!(reopened.text(), editor.text());
+    assert!(!reopened.history().can_undo());
+    assert!(!undo_path.exists());
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_undo_file_not_fitting_the_text_is_discarded() {
+    let file = "test_undo_file_not_fitting.txt";
+    fs::write(file, "").unwrap();
+
+    edit_and_save(file, "abc");
+    let undo_path = undo_file::undo_file_path(&scratch_undo_dir(), Path::new(file)).unwrap();
+    let stored = fs::read_to_string(&undo_path).unwrap();
+    let header: String = stored.split_inclusive('\n').take(3).collect();
+    let corruptions = [
+        // an offset past the end of the text
+        "undo 1\nstep single 0 0 3 0 1\ninsert 99 1\nx\nredo 0\n".to_string(),
+        // text that is not where the edit says
+        "undo 1\nstep single 0 0 3 0 1\ninsert 0 1\nz\nredo 0\n".to_string(),
+        // a cursor outside of the text
+        "undo 1\nstep single 0 7 3 0 1\ninsert 0 1\na\nredo 0\n".to_string(),
+        "undo 0\nredo 1\nstep single 0 0 0 0 1\ndelete 2 5\nabcde\n".to_string(),
+        // a length that cannot be allocated
+        format!(
+            "undo 1\nstep single 0 0 3 0 1\ninsert 0 {}\nabc\nredo 0\n",
+            usize::MAX
+        ),
+    ];
+    for history in corruptions {
+        fs::write(&undo_path, format!("{header}{history}")).unwrap();
+        let editor = open(file);
+        assert_eq!(editor.text(), "abc");
+        assert!(!editor.history().can_undo() && !editor.history().can_redo());
+        assert!(!undo_path.exists());
+    }
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn test_undo_history_is_kept_only_with_an_undo_dir() {
+    let file = "test_undo_history_without_dir.txt";
+    fs::write(file, "").unwrap();
+
+    edit_and_save(file, "abc");
+    assert!(!Editor::open(file).unwrap().history().can_undo());
+    // nothing edited yet, the history is still picked up
+    assert!(open(file).history().can_undo());
+
+    fs::remove_file(file).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_undo_files_are_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    let dir = scratch_dir("undo_file_private").join("undo");
+    let file = "test_undo_files_are_private.txt";
+    fs::write(file, "public").unwrap();
+
+    let mut editor = Editor::open(file).unwrap();
+    editor.set_undo_dir(Some(dir.clone()));
+    for event in type_text(" but deleted secret") {
+        editor.handle_event(event);
+    }
+    editor.save().unwrap();
+    assert_eq!(mode(&dir), 0o700);
+    let undo_path = undo_file::undo_file_path(&dir, Path::new(file)).unwrap();
+    assert_eq!(mode(&undo_path), 0o600);
+
+    fs::remove_file(file).unwrap();
+    fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_undo_dir_is_in_the_cache_directory() {
+    let cache = std::env::temp_dir().join("rust_terminal_notepad_undo_file_cache");
+    std::env::set_var("XDG_CACHE_HOME", &cache);
+    assert_eq!(
+        undo_file::undo_dir(),
+        Some(cache.join("rust_terminal_notepad").join("undo"))
+    );
+}
@@ -0,0 +1,107 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{key, type_text};
+use rust_terminal_notepad::unicode::{byte_offset, column_at_width, display_width, grapheme_count};
+use rust_terminal_notepad::{
+    line_length, position_to_index, render, Editor, EventSource, HeadlessBackend, ScriptedEvents,
+};
+
+fn run_headless(editor: &mut Editor, events: Vec<crossterm::event::Event>) -> HeadlessBackend {
+    let mut events = ScriptedEvents::new(events);
+    let mut screen = HeadlessBackend::new(30, 4);
+    while let Some(event) = events.read_event().unwrap() {
+        editor.handle_event(event);
+    }
+    render::draw(editor, &mut screen, true).unwrap();
+    screen
+}
+
+#[test]
+fn test_polish_text_editing() {
+    let mut editor = Editor::new("test_polish_text_editing.txt");
+    let mut events = type_text("zażółć gęślą");
+    events.push(key(KeyCode::Left, KeyModifiers::NONE));
+    events.push(key(KeyCode::Left, KeyModifiers::NONE));
+    events.extend(type_text("X"));
+    events.push(key(KeyCode::Backspace, KeyModifiers::NONE));
+    events.push(key(KeyCode::Backspace, KeyModifiers::NONE));
+    events.extend(type_text("ś"));
+
+    let screen = run_headless(&mut editor, events);
+
+    assert_eq!(editor.text(), "zażółć gęślą");
+    assert_eq!(editor.cursor(), (10, 0));
+    assert_eq!(screen.line(0), "zażółć gęślą");
+    assert_eq!(screen.cursor(), (10, 0));
+}
+
+#[test]
+fn test_cjk_wide_characters() {
+    let mut editor = Editor::new("test_cjk_wide_characters.txt");
+    let mut events = type_text("日本語abc\nxyz12345");
+    events.push(key(KeyCode::Up, KeyModifiers::NONE));
+
+    let screen = run_headless(&mut editor, events);
+
+    // screen column 8 on the second line is right after "語" on the first
+    assert_eq!(editor.cursor(), (5, 0));
+    assert_eq!(screen.cursor(), (8, 0));
+    assert_eq!(screen.cell(0, 0).symbol, "日");
+    assert_eq!(screen.cell(1, 0).symbol, "");
+    assert_eq!(screen.cell(6, 0).symbol, "a");
+    assert_eq!(screen.line(0), "日本語abc");
+
+    let text = editor.text();
+    assert_eq!(line_length(text.as_str(), 0), 6);
+    assert_eq!(position_to_index(text.as_str(), (3, 0)), 9);
+}
+
+#[test]
+fn test_emoji_and_zero_width_joiners() {
+    let family = "👨‍👩‍👧";
+    let mut editor = Editor::new("test_emoji_and_zero_width_joiners.txt");
+    let mut events = type_text(&format!("a{family}b🇵🇱"));
+    events.push(key(KeyCode::Left, KeyModifiers::NONE));
+    events.push(key(KeyCode::Left, KeyModifiers::NONE));
+    events.push(key(KeyCode::Backspace, KeyModifiers::NONE));
+
+    let screen = run_headless(&mut editor, events);
+
+    // the whole family is one grapheme, deleted with a single Backspace
+    assert_eq!(editor.text(), "ab🇵🇱");
+    assert_eq!(editor.cursor(), (1, 0));
+    assert_eq!(screen.cursor(), (1, 0));
+
+    assert_eq!(grapheme_count(family), 1);
+    assert_eq!(display_width(family), 2);
+    assert_eq!(display_width("🇵🇱"), 2);
+    assert_eq!(byte_offset(&format!("a{family}b"), 2), 1 + family.len());
+}
+
+#[test]
+fn test_combining_marks_join_the_previous_character() {
+    let mut editor = Editor::new("test_combining_marks.txt");
+    let events = vec![
+        key(KeyCode::Char('e'), KeyModifiers::NONE),
+        key(KeyCode::Char('\u{301}'), KeyModifiers::NONE),
+        key(KeyCode::Char('x'), KeyModifiers::NONE),
+    ];
+
+    let screen = run_headless(&mut editor, events);
+
+    assert_eq!(editor.text(), "e\u{301}x");
+    assert_eq!(editor.cursor(), (2, 0));
+    assert_eq!(screen.cell(0, 0).symbol, "e\u{301}");
+    assert_eq!(screen.cursor(), (2, 0));
+}
+
+#[test]
+fn test_column_at_width() {
+    assert_eq!(column_at_width("日本語", 0), 0);
+    assert_eq!(column_at_width("日本語", 3), 1);
+    assert_eq!(column_at_width("日本語", 4), 2);
+    assert_eq!(column_at_width("日本語", 10), 3);
+    assert_eq!(column_at_width("a\tb", 3), 1);
+}
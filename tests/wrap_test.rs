@@ -0,0 +1,109 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+mod common;
+
+use common::{editor_set_up_with, key, screen_of};
+use rust_terminal_notepad::wrap::rows;
+use rust_terminal_notepad::{Editor, Wrap};
+
+fn editor_with(text: &str, width: u16, height: u16, wrap: Wrap) -> Editor {
+    editor_set_up_with("test_wrap.txt", text, |editor| {
+        editor.resize(width, height);
+        editor.set_wrap(wrap);
+    })
+}
+
+#[test]
+fn test_rows_break_anywhere_or_at_words() {
+    let line = "the quick brown fox";
+    assert_eq!(
+        rows(line, 8, Wrap::Off),
+        std::iter::once(0..19).collect::<Vec<_>>()
+    );
+    // rows after the first lose a cell to the continuation marker
+    assert_eq!(rows(line, 8, Wrap::Anywhere), vec![0..8, 8..15, 15..19]);
+    assert_eq!(
+        rows(line, 8, Wrap::Words),
+        vec![0..4, 4..10, 10..16, 16..19]
+    );
+    // a word longer than a row is broken anyway
+    assert_eq!(rows("abcdefghij", 6, Wrap::Words), vec![0..6, 6..10]);
+    // a wide character which does not fit moves to the next row
+    assert_eq!(rows("ab日本", 5, Wrap::Anywhere), vec![0..3, 3..4]);
+    assert_eq!(
+        rows("", 5, Wrap::Words),
+        std::iter::once(0..0).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_wrapped_lines_are_drawn_with_marker() {
+    let editor = editor_with("the quick brown fox\nend", 8, 6, Wrap::Words);
+    let screen = screen_of(&editor, 8, 6);
+    assert_eq!(
+        screen.lines(),
+        vec!["the", "↪quick", "↪brown", "↪fox", "end", "test_wra"]
+    );
+    assert_eq!(screen.cursor(), (3, 4));
+}
+
+#[test]
+fn test_up_and_down_move_by_screen_rows() {
+    let mut editor = editor_with("abcdefghijkl\nxy", 6, 6, Wrap::Anywhere);
+    // rows: "abcdef", "↪ghijk", "↪l", "xy"
+    editor.handle_event(key(KeyCode::Up, KeyModifiers::NONE));
+    assert_eq!(editor.cursor(), (12, 0));
+    assert_eq!(editor.screen_cursor(), (2, 2));
+
+    editor.handle_event(key(KeyCode::Up, KeyModifiers::NONE));
+    assert_eq!(editor.cursor(), (7, 0));
+    assert_eq!(editor.screen_cursor(), (2, 1));
+
+    editor.handle_event(key(KeyCode::Up, KeyModifiers::NONE));
+    assert_eq!(editor.cursor(), (2, 0));
+
+    // past the end of a row that goes on, the cursor stays in the row
+    for _ in 0..3 {
+        editor.handle_event(key(KeyCode::Right, KeyModifiers::NONE));
+    }
+    editor.handle_event(key(KeyCode::Down, KeyModifiers::NONE));
+    assert_eq!(editor.cursor(), (10, 0));
+    editor.handle_event(key(KeyCode::Down, KeyModifiers::NONE));
+    assert_eq!(editor.cursor(), (12, 0));
+    editor.handle_event(key(KeyCode::Down, KeyModifiers::NONE));
+    assert_eq!(editor.cursor(), (2, 1));
+}
+
+#[test]
+fn test_wrapped_viewport_scrolls_by_rows() {
+    let mut editor = editor_with("one\nabcdefghijkl\ntwo", 6, 4, Wrap::Anywhere);
+    // three rows of text: the end of the long line and "two"
+    let screen = screen_of(&editor, 6, 4);
+    assert_eq!(screen.lines(), vec!["↪ghijk", "↪l", "two", "test_w"]);
+    assert_eq!(editor.viewport().top, 1);
+    assert_eq!(editor.viewport().top_row, 1);
+
+    editor.handle_event(key(KeyCode::Up, KeyModifiers::NONE));
+    editor.handle_event(key(KeyCode::Up, KeyModifiers::NONE));
+    editor.handle_event(key(KeyCode::Up, KeyModifiers::NONE));
+    let screen = screen_of(&editor, 6, 4);
+    assert_eq!(screen.lines(), vec!["abcdef", "↪ghijk", "↪l", "test_w"]);
+    assert_eq!(screen.cursor(), (2, 0));
+}
+
+#[test]
+fn test_alt_z_toggles_wrapping() {
+    let mut editor = editor_with("abcdefghij", 6, 3, Wrap::Off);
+    assert_eq!(screen_of(&editor, 6, 3).line(0), "fghij");
+
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::ALT));
+    assert_eq!(editor.wrap(), Wrap::Words);
+    assert_eq!(editor.message(), Some("Line wrapping on"));
+    let screen = screen_of(&editor, 6, 3);
+    assert_eq!(screen.lines(), vec!["abcdef", "↪ghij", "test_w"]);
+    assert_eq!(screen.cursor(), (5, 1));
+
+    editor.handle_event(key(KeyCode::Char('z'), KeyModifiers::ALT));
+    assert_eq!(editor.wrap(), Wrap::Off);
+    assert_eq!(editor.text(), "abcdefghij");
+}